@@ -0,0 +1,124 @@
+use std::env;
+use std::io::{self, BufRead};
+
+const TARGET: i64 = 2020;
+
+// Finds `k` entries of `sorted` (which must be sorted ascending) that sum to
+// `target`, fixing the smallest entry and recursing until only a pair is left,
+// which is found by walking two pointers in from either end.
+fn k_sum(sorted: &[i64], target: i64, k: usize) -> Option<Vec<i64>> {
+    match k {
+        0 => None,
+        1 => sorted.binary_search(&target).ok().map(|i| vec![sorted[i]]),
+        2 => {
+            if sorted.is_empty() {
+                return None;
+            }
+            let (mut lo, mut hi) = (0, sorted.len() - 1);
+            while lo < hi {
+                let sum = sorted[lo] + sorted[hi];
+                if sum == target {
+                    return Some(vec![sorted[lo], sorted[hi]]);
+                } else if sum < target {
+                    lo += 1;
+                } else {
+                    hi -= 1;
+                }
+            }
+            None
+        }
+        _ => {
+            for (i, n) in sorted.iter().enumerate() {
+                if let Some(mut rest) = k_sum(&sorted[i + 1..], target - n, k - 1) {
+                    rest.insert(0, *n);
+                    return Some(rest);
+                }
+            }
+            None
+        }
+    }
+}
+
+fn find_entries(entries: &[i64], target: i64, k: usize) -> Option<Vec<i64>> {
+    let mut sorted = entries.to_vec();
+    sorted.sort_unstable();
+    k_sum(&sorted, target, k)
+}
+
+fn main() {
+    // Optionally `<target> <k>` to search for something other than the
+    // puzzle's pair and triple summing to 2020.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let target = args.first().map_or(TARGET, |a| a.parse().unwrap());
+
+    let stdin = io::stdin();
+    let entries: Vec<i64> = stdin
+        .lock()
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.parse().unwrap())
+        .collect();
+
+    let ks: Vec<usize> = match args.get(1) {
+        Some(k) => vec![k.parse().unwrap()],
+        None => vec![2, 3],
+    };
+
+    for k in ks {
+        match find_entries(&entries, target, k) {
+            Some(found) => println!("{:?} {}", found, found.iter().product::<i64>()),
+            None => println!("no {} entries sum to {}", k, target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<i64> {
+        include_str!("../tiny_input")
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_pair() {
+        let found = find_entries(&example(), TARGET, 2).unwrap();
+        assert_eq!(found, vec![299, 1721]);
+        assert_eq!(found.iter().product::<i64>(), 514579);
+    }
+
+    #[test]
+    fn test_triple() {
+        let found = find_entries(&example(), TARGET, 3).unwrap();
+        assert_eq!(found, vec![366, 675, 979]);
+        assert_eq!(found.iter().product::<i64>(), 241861950);
+    }
+
+    #[test]
+    fn test_other_targets() {
+        let entries = example();
+        assert_eq!(find_entries(&entries, 979, 1), Some(vec![979]));
+        assert_eq!(find_entries(&entries, 665, 2), Some(vec![299, 366]));
+        assert_eq!(
+            find_entries(&entries, 299 + 366 + 675 + 979, 4).map(|v| v.len()),
+            Some(4)
+        );
+        assert_eq!(find_entries(&entries, 1, 2), None);
+        assert_eq!(find_entries(&entries, TARGET, 0), None);
+        assert_eq!(find_entries(&entries, TARGET, 7), None);
+    }
+
+    #[test]
+    fn test_no_reuse() {
+        // 1010 only appears once, so it can't pair with itself.
+        assert_eq!(find_entries(&[1010, 3, 5], TARGET, 2), None);
+        assert_eq!(
+            find_entries(&[1010, 1010], TARGET, 2),
+            Some(vec![1010, 1010])
+        );
+    }
+}
@@ -1,83 +1,664 @@
-use regex::Regex;
+use std::env;
 use std::io::{self, BufRead};
+use std::process;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 struct Policy {
     character: char,
     range: (u32, u32),
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 struct Password {
     policy: Policy,
     password: String,
 }
 
-impl Policy {
-    fn matches_first_policy(&self, password: &String) -> bool {
-        let c = password.chars().filter(|c| *c == self.character).count();
-        let ok = c >= self.range.0 as usize && c <= self.range.1 as usize;
-        //println!("range: {:?}, count: {}, ok: {}", self.range, c, ok);
-        ok
+impl Password {
+    // Parses `<min>-<max> <char>: <password>` by hand, which is much faster
+    // than running a regex over every line of a large file.
+    fn parse(st: &str) -> Result<Password, String> {
+        let err = || format!("could not parse {:?}", st);
+
+        let (range, rest) = st.split_once(' ').ok_or_else(err)?;
+        let (min, max) = range.split_once('-').ok_or_else(err)?;
+        let (character, password) = rest.split_once(": ").ok_or_else(err)?;
+
+        let mut chars = character.chars();
+        let character = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => return Err(err()),
+        };
+        if password.is_empty() || password.contains(char::is_whitespace) {
+            return Err(err());
+        }
+
+        Ok(Password {
+            policy: Policy {
+                character,
+                range: (
+                    min.parse().map_err(|_| err())?,
+                    max.parse().map_err(|_| err())?,
+                ),
+            },
+            password: password.to_string(),
+        })
+    }
+}
+
+// A rule a password can be checked against. `check` explains why a password
+// was rejected.
+trait PasswordPolicy {
+    fn name(&self) -> String;
+    fn check(&self, pw: &Password) -> Result<(), String>;
+}
+
+// The character must appear between `range.0` and `range.1` times.
+struct CountInRange;
+
+impl PasswordPolicy for CountInRange {
+    fn name(&self) -> String {
+        "count".to_string()
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), String> {
+        let p = &pw.policy;
+        let c = pw.password.chars().filter(|c| *c == p.character).count();
+        if c >= p.range.0 as usize && c <= p.range.1 as usize {
+            Ok(())
+        } else {
+            Err(format!(
+                "{:?} appears {} times, expected {}-{}",
+                p.character, c, p.range.0, p.range.1
+            ))
+        }
+    }
+}
+
+// Which of the two (1-indexed) positions in `range` hold the character.
+fn positions_matching(pw: &Password) -> (bool, bool) {
+    let p = &pw.policy;
+    let at = |i: u32| {
+        i.checked_sub(1)
+            .and_then(|i| pw.password.chars().nth(i as usize))
+            == Some(p.character)
+    };
+    (at(p.range.0), at(p.range.1))
+}
+
+// Exactly one of the two positions must hold the character.
+struct ExactlyOnePosition;
+
+impl PasswordPolicy for ExactlyOnePosition {
+    fn name(&self) -> String {
+        "xor".to_string()
     }
 
-    fn matches_second_policy(&self, password: &String) -> bool {
-        let mut count = 0;
-        let first_c = password.chars().nth(self.range.0 as usize - 1);
-        if first_c == Some(self.character) {
-            count += 1;
+    fn check(&self, pw: &Password) -> Result<(), String> {
+        let p = &pw.policy;
+        match positions_matching(pw) {
+            (true, false) | (false, true) => Ok(()),
+            (true, true) => Err(format!(
+                "{:?} is at both positions {} and {}",
+                p.character, p.range.0, p.range.1
+            )),
+            (false, false) => Err(format!(
+                "{:?} is at neither position {} nor {}",
+                p.character, p.range.0, p.range.1
+            )),
         }
+    }
+}
+
+// Both positions must hold the character.
+struct BothPositions;
 
-        let second_c = password.chars().nth(self.range.1 as usize - 1);
-        if second_c == Some(self.character) {
-            count += 1;
+impl PasswordPolicy for BothPositions {
+    fn name(&self) -> String {
+        "and".to_string()
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), String> {
+        let p = &pw.policy;
+        match positions_matching(pw) {
+            (true, true) => Ok(()),
+            (first, _) => Err(format!(
+                "{:?} is not at position {}",
+                p.character,
+                if first { p.range.1 } else { p.range.0 }
+            )),
         }
+    }
+}
 
-        println!(
-            "first_c ({:?}): {:?}, second_c ({:?}): {:?}, pw: {:?}",
-            first_c, self.range.0, second_c, self.range.1, password,
-        );
-        count == 1
+struct MinLength(usize);
+
+impl PasswordPolicy for MinLength {
+    fn name(&self) -> String {
+        format!("min-length:{}", self.0)
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), String> {
+        let len = pw.password.chars().count();
+        if len >= self.0 {
+            Ok(())
+        } else {
+            Err(format!("length {} is shorter than {}", len, self.0))
+        }
     }
 }
 
-impl Password {
-    fn valid(&self) -> bool {
-        self.policy.matches_second_policy(&self.password)
+struct MaxLength(usize);
+
+impl PasswordPolicy for MaxLength {
+    fn name(&self) -> String {
+        format!("max-length:{}", self.0)
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), String> {
+        let len = pw.password.chars().count();
+        if len <= self.0 {
+            Ok(())
+        } else {
+            Err(format!("length {} is longer than {}", len, self.0))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    fn parse(st: &str) -> Result<CharClass, String> {
+        use CharClass::*;
+        match st {
+            "lower" => Ok(Lower),
+            "upper" => Ok(Upper),
+            "digit" => Ok(Digit),
+            "symbol" => Ok(Symbol),
+            _ => Err(format!("unknown character class: {}", st)),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        use CharClass::*;
+        match self {
+            Lower => "lower",
+            Upper => "upper",
+            Digit => "digit",
+            Symbol => "symbol",
+        }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        use CharClass::*;
+        match self {
+            Lower => c.is_lowercase(),
+            Upper => c.is_uppercase(),
+            Digit => c.is_ascii_digit(),
+            Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+// Each class must be represented by at least one character.
+struct RequiredClasses(Vec<CharClass>);
+
+impl PasswordPolicy for RequiredClasses {
+    fn name(&self) -> String {
+        let names: Vec<&str> = self.0.iter().map(|c| c.name()).collect();
+        format!("require:{}", names.join(","))
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), String> {
+        let missing: Vec<&str> = self
+            .0
+            .iter()
+            .filter(|class| !pw.password.chars().any(|c| class.matches(c)))
+            .map(|class| class.name())
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("no {} characters", missing.join(", ")))
+        }
+    }
+}
+
+// No character may appear more than the given number of times in a row.
+struct NoRepeats(usize);
+
+impl PasswordPolicy for NoRepeats {
+    fn name(&self) -> String {
+        format!("no-repeats:{}", self.0)
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), String> {
+        let mut prev = None;
+        let mut run = 0;
+        for c in pw.password.chars() {
+            run = if prev == Some(c) { run + 1 } else { 1 };
+            if run > self.0 {
+                return Err(format!("{:?} repeats more than {} times", c, self.0));
+            }
+            prev = Some(c);
+        }
+        Ok(())
+    }
+}
+
+// Parses a policy named on the command line, such as `count`, `xor`, `and`,
+// `min-length:8`, `max-length:20`, `require:lower,digit` or `no-repeats:2`.
+fn parse_policy(st: &str) -> Result<Box<dyn PasswordPolicy>, String> {
+    let (name, arg) = match st.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (st, None),
+    };
+    let number = || -> Result<usize, String> {
+        arg.ok_or(format!("{} needs a number", name))?
+            .parse()
+            .map_err(|e| format!("{}: {}", name, e))
+    };
+
+    match name {
+        "count" => Ok(Box::new(CountInRange)),
+        "xor" => Ok(Box::new(ExactlyOnePosition)),
+        "and" => Ok(Box::new(BothPositions)),
+        "min-length" => Ok(Box::new(MinLength(number()?))),
+        "max-length" => Ok(Box::new(MaxLength(number()?))),
+        "no-repeats" => Ok(Box::new(NoRepeats(number()?))),
+        "require" => Ok(Box::new(RequiredClasses(
+            arg.ok_or("require needs character classes")?
+                .split(',')
+                .map(CharClass::parse)
+                .collect::<Result<_, _>>()?,
+        ))),
+        _ => Err(format!("unknown policy: {}", st)),
+    }
+}
+
+#[derive(Default, Debug)]
+struct Report {
+    valid: usize,
+    // Line numbers (counting from 1) and the reason each was rejected.
+    failures: Vec<(usize, String)>,
+}
+
+fn validate(passwords: &[(usize, Password)], policy: &dyn PasswordPolicy) -> Report {
+    let mut report = Report::default();
+    for (line, pw) in passwords {
+        match policy.check(pw) {
+            Ok(()) => report.valid += 1,
+            Err(reason) => report.failures.push((*line, reason)),
+        }
+    }
+    report
+}
+
+// Each password with its line number, counting from 1. Blank lines are
+// skipped, as in `validate_stream`, but still counted.
+fn parse_passwords(lines: impl Iterator<Item = String>) -> Result<Vec<(usize, Password)>, String> {
+    lines
+        .enumerate()
+        .filter(|(_, li)| !li.is_empty())
+        .map(|(i, li)| {
+            Password::parse(&li)
+                .map(|pw| (i + 1, pw))
+                .map_err(|e| format!("line {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+#[derive(Default, Debug, PartialEq)]
+struct StreamSummary {
+    lines: u64,
+    malformed: u64,
+    // How many lines passed each policy, in the order they were given.
+    valid: Vec<u64>,
+}
+
+// Validates one line at a time, reusing a single buffer, so memory use stays
+// flat however large the input is. `on_failure` is called with the index of
+// the failing policy (or `None` for a line that doesn't parse), the line
+// number and the reason.
+fn validate_stream(
+    mut reader: impl BufRead,
+    policies: &[Box<dyn PasswordPolicy>],
+    mut on_failure: impl FnMut(Option<usize>, u64, &str),
+) -> io::Result<StreamSummary> {
+    let mut summary = StreamSummary {
+        valid: vec![0; policies.len()],
+        ..Default::default()
+    };
+
+    // Blank lines aren't counted in the summary, but do count towards the
+    // line numbers reported.
+    let mut line_number: u64 = 0;
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            break;
+        }
+        line_number += 1;
+        let line = buf.trim_end_matches(&['\n', '\r'][..]);
+        if line.is_empty() {
+            continue;
+        }
+        summary.lines += 1;
+
+        let pw = match Password::parse(line) {
+            Ok(pw) => pw,
+            Err(reason) => {
+                summary.malformed += 1;
+                on_failure(None, line_number, &reason);
+                continue;
+            }
+        };
+
+        for (i, policy) in policies.iter().enumerate() {
+            match policy.check(&pw) {
+                Ok(()) => summary.valid[i] += 1,
+                Err(reason) => on_failure(Some(i), line_number, &reason),
+            }
+        }
     }
+
+    Ok(summary)
 }
 
-fn parse_passwords() -> Result<Vec<Password>, io::Error> {
-    let re = Regex::new(r"(?P<min>\d+)-(?P<max>\d+) (?P<ch>\w): (?P<pw>\w+)").unwrap();
+const USAGE: &str = "usage: aoc2 [--stream [--lines]] [policy...]";
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{}; {}", problem, USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    // `--stream` validates line by line without holding the file in memory,
+    // printing only counts unless `--lines` asks for the failing lines too.
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if let Some(flag) = args
+        .iter()
+        .find(|a| a.starts_with("--") && *a != "--stream" && *a != "--lines")
+    {
+        usage(&format!("unknown flag {}", flag));
+    }
+    let stream = args.iter().any(|a| a == "--stream");
+    let show_lines = args.iter().any(|a| a == "--lines");
+    args.retain(|a| !a.starts_with("--"));
+    if args.is_empty() {
+        args = vec!["count".to_string(), "xor".to_string()];
+    }
+    let policies: Vec<Box<dyn PasswordPolicy>> = args
+        .iter()
+        .map(|a| parse_policy(a).unwrap_or_else(|e| usage(&e)))
+        .collect();
 
     let stdin = io::stdin();
-    let lines = stdin.lock().lines();
 
-    let passwords: Vec<Password> = lines
-        .map(|line| {
-            let li = line.unwrap();
-            let caps = re.captures(li.as_str()).unwrap();
+    if stream {
+        let summary = validate_stream(stdin.lock(), &policies, |policy, line, reason| {
+            if show_lines {
+                let name = policy.map_or("parse".to_string(), |i| policies[i].name());
+                println!("{}: line {}: {}", name, line, reason);
+            }
+        })
+        .unwrap();
+
+        println!("{} lines, {} malformed", summary.lines, summary.malformed);
+        for (policy, valid) in policies.iter().zip(&summary.valid) {
+            println!("{}: {} valid", policy.name(), valid);
+        }
+        return;
+    }
+
+    let lines = stdin.lock().lines().map(|line| line.unwrap());
+    let passwords = parse_passwords(lines).unwrap();
+
+    for policy in &policies {
+        let report = validate(&passwords, policy.as_ref());
+        for (line, reason) in &report.failures {
+            println!("{}: line {}: {}", policy.name(), line, reason);
+        }
+        println!("{}: {} valid", policy.name(), report.valid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<(usize, Password)> {
+        parse_passwords(
+            ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
+                .iter()
+                .map(|l| l.to_string()),
+        )
+        .unwrap()
+    }
+
+    fn password(st: &str) -> Password {
+        Password {
+            policy: Policy {
+                character: 'a',
+                range: (1, 2),
+            },
+            password: st.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_example() {
+        let passwords = example();
+
+        let count = validate(&passwords, &CountInRange);
+        assert_eq!(count.valid, 2);
+        assert_eq!(
+            count.failures,
+            vec![(2, "'b' appears 0 times, expected 1-3".to_string())]
+        );
+
+        let xor = validate(&passwords, &ExactlyOnePosition);
+        assert_eq!(xor.valid, 1);
+        assert_eq!(xor.failures[0].0, 2);
+        assert_eq!(
+            xor.failures[1],
+            (3, "'c' is at both positions 2 and 9".to_string())
+        );
+
+        assert_eq!(validate(&passwords, &BothPositions).valid, 1);
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        for name in [
+            "count",
+            "xor",
+            "and",
+            "min-length:8",
+            "max-length:20",
+            "require:lower,digit",
+            "no-repeats:2",
+        ] {
+            assert_eq!(parse_policy(name).unwrap().name(), name);
+        }
+
+        assert!(parse_policy("min-length").is_err());
+        assert!(parse_policy("min-length:eight").is_err());
+        assert!(parse_policy("require:emoji").is_err());
+        assert!(parse_policy("nope").is_err());
+    }
+
+    #[test]
+    fn test_length() {
+        assert!(MinLength(3).check(&password("abc")).is_ok());
+        assert!(MinLength(4).check(&password("abc")).is_err());
+        assert!(MaxLength(3).check(&password("abc")).is_ok());
+        assert!(MaxLength(2).check(&password("abc")).is_err());
+    }
+
+    #[test]
+    fn test_required_classes() {
+        let policy = parse_policy("require:lower,upper,digit,symbol").unwrap();
+        assert!(policy.check(&password("aB3!")).is_ok());
+        assert_eq!(
+            policy.check(&password("ab")),
+            Err("no upper, digit, symbol characters".to_string())
+        );
+    }
+
+    #[test]
+    fn test_no_repeats() {
+        assert!(NoRepeats(2).check(&password("aabba")).is_ok());
+        assert_eq!(
+            NoRepeats(2).check(&password("abbb")),
+            Err("'b' repeats more than 2 times".to_string())
+        );
+    }
+
+    #[test]
+    fn test_positions_out_of_range() {
+        let mut pw = password("a");
+        pw.policy.range = (0, 5);
+        assert!(ExactlyOnePosition.check(&pw).is_err());
+        assert!(BothPositions.check(&pw).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Password::parse("12-345 x: xyz").unwrap(),
             Password {
                 policy: Policy {
-                    character: caps["ch"].chars().nth(0).unwrap(),
-                    range: (caps["min"].parse().unwrap(), caps["max"].parse().unwrap()),
+                    character: 'x',
+                    range: (12, 345),
                 },
-                password: caps["pw"].to_string(),
+                password: "xyz".to_string(),
             }
+        );
+
+        for st in [
+            "",
+            "1-3 a abcde",
+            "1 a: abcde",
+            "1-b a: abcde",
+            "1-3 ab: abcde",
+            "1-3 a: ",
+            "1-3 a: ab cde",
+        ]
+        .iter()
+        {
+            assert!(Password::parse(st).is_err(), "{:?}", st);
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let input = "1-3 a: abcde\r\n1-3 b: cdefg\n\nnonsense\n2-9 c: ccccccccc";
+        let policies = vec![parse_policy("count").unwrap(), parse_policy("xor").unwrap()];
+
+        let mut failures = vec![];
+        let summary = validate_stream(input.as_bytes(), &policies, |policy, line, _| {
+            failures.push((policy, line))
         })
-        .collect();
+        .unwrap();
 
-    Ok(passwords)
-}
+        assert_eq!(
+            summary,
+            StreamSummary {
+                lines: 4,
+                malformed: 1,
+                valid: vec![2, 1],
+            }
+        );
+        assert_eq!(
+            failures,
+            vec![(Some(0), 2), (Some(1), 2), (None, 4), (Some(1), 5)]
+        );
+    }
 
-fn main() {
-    let parsed_passwords = parse_passwords().unwrap();
-    let mut count_valid = 0;
-    for pw in parsed_passwords {
-        if pw.valid() {
-            count_valid += 1;
+    // Produces `n` generated lines on demand rather than holding them.
+    struct Generated {
+        n: u64,
+        next: u64,
+        pending: Vec<u8>,
+    }
+
+    impl io::Read for Generated {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pending.is_empty() {
+                if self.next == self.n {
+                    return Ok(0);
+                }
+                let line = if self.next.is_multiple_of(2) {
+                    "1-3 a: abcde\n"
+                } else {
+                    "1-3 b: cdefg\n"
+                };
+                self.pending = line.as_bytes().to_vec();
+                self.next += 1;
+            }
+            let len = self.pending.len().min(buf.len());
+            buf[..len].copy_from_slice(&self.pending[..len]);
+            self.pending.drain(..len);
+            Ok(len)
         }
     }
 
-    println!("{}", count_valid)
+    #[test]
+    fn test_stream_generated() {
+        let n = 200_000;
+        let reader = io::BufReader::new(Generated {
+            n,
+            next: 0,
+            pending: vec![],
+        });
+        let policies = vec![parse_policy("count").unwrap()];
+
+        let mut failures = 0;
+        let summary = validate_stream(reader, &policies, |_, _, _| failures += 1).unwrap();
+        assert_eq!(summary.lines, n);
+        assert_eq!(summary.valid, vec![n / 2]);
+        assert_eq!(failures, n / 2);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(parse_passwords(vec!["1-3 a abcde".to_string()].into_iter()).is_err());
+        // Too big for the range, which mustn't panic.
+        assert!(
+            parse_passwords(vec!["1-99999999999999999999 a: abcde".to_string()].into_iter())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_blank_lines() {
+        let lines = ["1-3 a: abcde", "", "1-3 b: cdefg", "", "nonsense"];
+        let err = parse_passwords(lines.iter().map(|l| l.to_string())).unwrap_err();
+        assert!(err.starts_with("line 5:"), "{}", err);
+
+        let passwords = parse_passwords(lines[..4].iter().map(|l| l.to_string())).unwrap();
+        let report = validate(&passwords, &CountInRange);
+        assert_eq!(
+            report.failures,
+            vec![(3, "'b' appears 0 times, expected 1-3".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_symbols() {
+        // The whole password is kept, not just its leading word characters.
+        let passwords = parse_passwords(vec!["1-3 a: aB3$cd".to_string()].into_iter()).unwrap();
+        assert_eq!(passwords[0].1.password, "aB3$cd");
+
+        let policy = parse_policy("require:lower,upper,digit,symbol").unwrap();
+        assert_eq!(validate(&passwords, policy.as_ref()).valid, 1);
+    }
 }
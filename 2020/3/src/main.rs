@@ -1,37 +1,229 @@
-use std::io::{self, BufRead, Error, Lines};
-use std::iter::Iterator;
-
-fn num_trees(
-    lines: &mut dyn Iterator<Item = &Result<String, Error>>,
-    slope: (usize, usize),
-) -> u32 {
-    let mut x = 0;
-    let mut y = 0;
-    let mut trees = 0;
-    for line in lines.step_by(slope.1) {
-        let li = line.as_ref().unwrap();
-        let c = li.chars().nth(x % li.len());
-        if c == Some('#') {
-            trees += 1;
+use std::env;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::process;
+
+const SLOPES: [(usize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+
+// A map that repeats forever to the right.
+#[derive(Debug)]
+struct Map {
+    rows: Vec<Vec<bool>>,
+    width: usize,
+}
+
+impl Map {
+    fn parse(lines: impl Iterator<Item = String>) -> Result<Map, String> {
+        let rows = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.chars()
+                    .map(|ch| match ch {
+                        '#' => Ok(true),
+                        '.' => Ok(false),
+                        _ => Err(format!("unexpected square: {}", ch)),
+                    })
+                    .collect::<Result<Vec<bool>, String>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = rows.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err("empty map".to_string());
+        }
+        if let Some(i) = rows.iter().position(|row| row.len() != width) {
+            return Err(format!("row {} is not {} wide", i + 1, width));
+        }
+        Ok(Map { rows, width })
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn is_tree(&self, x: usize, y: usize) -> bool {
+        self.rows[y][x % self.width]
+    }
+
+    // Every square visited going `right` and `down` each step from the top
+    // left until falling off the bottom.
+    fn path(&self, (right, down): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        (0..self.height())
+            .step_by(down)
+            .enumerate()
+            .map(move |(i, y)| (i * right, y))
+    }
+
+    fn trees(&self, slope: (usize, usize)) -> usize {
+        self.path(slope)
+            .filter(|&(x, y)| self.is_tree(x, y))
+            .count()
+    }
+
+    // Trees hit on every slope whose right and down steps are at most
+    // `bound`, taking each ratio once in its lowest terms. Going straight
+    // down is included, but going sideways forever isn't.
+    fn all_slopes(&self, bound: usize) -> Vec<((usize, usize), usize)> {
+        let mut v = vec![];
+        for down in 1..=bound {
+            for right in 0..=bound {
+                if gcd(right, down) == 1 {
+                    v.push(((right, down), self.trees((right, down))));
+                }
+            }
+        }
+        v
+    }
+
+    fn fewest_trees(&self, bound: usize) -> Option<((usize, usize), usize)> {
+        self.all_slopes(bound)
+            .into_iter()
+            .min_by_key(|&(_, trees)| trees)
+    }
+
+    fn most_trees(&self, bound: usize) -> Option<((usize, usize), usize)> {
+        self.all_slopes(bound)
+            .into_iter()
+            .max_by_key(|&(_, trees)| trees)
+    }
+
+    // Draws the map with the path folded back into a single tile: `X` where
+    // it hits a tree and `O` where it passes through open ground.
+    fn render(&self, slope: (usize, usize)) -> Trail<'_> {
+        let mut visited = vec![vec![false; self.width]; self.height()];
+        for (x, y) in self.path(slope) {
+            visited[y][x % self.width] = true;
         }
-        x += slope.0;
-        y += slope.1;
-        //println!("({},{})", x, y);
+        Trail { map: self, visited }
     }
-    trees
+}
+
+struct Trail<'a> {
+    map: &'a Map,
+    visited: Vec<Vec<bool>>,
+}
+
+impl fmt::Display for Trail<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (row, visited) in self.map.rows.iter().zip(&self.visited) {
+            for (tree, visited) in row.iter().zip(visited) {
+                let ch = match (tree, visited) {
+                    (true, true) => 'X',
+                    (false, true) => 'O',
+                    (true, false) => '#',
+                    (false, false) => '.',
+                };
+                write!(f, "{}", ch)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+const USAGE: &str = "usage: aoc3 [bound], where bound is at least 1";
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{}; {}", problem, USAGE);
+    process::exit(2);
 }
 
 fn main() {
+    // Optionally a bound to search every slope up to, instead of the
+    // puzzle's five.
+    let bound: Option<usize> = env::args().nth(1).map(|a| match a.parse() {
+        Ok(0) | Err(_) => usage(&format!("bad bound {}", a)),
+        Ok(bound) => bound,
+    });
+
     let stdin = io::stdin();
-    let lines = stdin.lock().lines();
-    let collected: Vec<Result<String, Error>> = lines.collect();
-    let slopes = vec![(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+    let map = Map::parse(stdin.lock().lines().map(|line| line.unwrap())).unwrap();
+
+    if let Some(bound) = bound {
+        // Any bound from 1 has at least the (0, 1) and (1, 1) slopes.
+        let (fewest, trees) = map.fewest_trees(bound).unwrap();
+        println!("fewest: {:?} hits {}", fewest, trees);
+        let (most, trees) = map.most_trees(bound).unwrap();
+        println!("most: {:?} hits {}", most, trees);
+        print!("{}", map.render(fewest));
+        return;
+    }
 
     let mut total = 1;
-    for slope in slopes {
-        let num = num_trees(&mut collected.iter(), slope);
+    for slope in SLOPES.iter() {
+        let num = map.trees(*slope);
         println!("{}", num);
         total *= num;
     }
     println!("{}", total)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Map {
+        Map::parse(include_str!("../tiny_input").lines().map(|l| l.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_trees() {
+        let map = example();
+        let trees: Vec<usize> = SLOPES.iter().map(|s| map.trees(*s)).collect();
+        assert_eq!(trees, vec![2, 7, 3, 4, 2]);
+        assert_eq!(trees.iter().product::<usize>(), 336);
+    }
+
+    #[test]
+    fn test_wraps() {
+        let map = example();
+        assert!(map.is_tree(2, 0));
+        assert!(map.is_tree(2 + 11 * 3, 0));
+        assert!(!map.is_tree(11, 0));
+    }
+
+    #[test]
+    fn test_all_slopes() {
+        let map = example();
+        let slopes = map.all_slopes(2);
+        assert_eq!(
+            slopes.iter().map(|(s, _)| *s).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 1), (2, 1), (1, 2)]
+        );
+        for (slope, trees) in slopes {
+            assert_eq!(trees, map.trees(slope));
+        }
+
+        assert_eq!(map.fewest_trees(7), Some(((5, 2), 0)));
+        assert_eq!(map.most_trees(7), Some(((3, 1), 7)));
+    }
+
+    #[test]
+    fn test_render() {
+        let rendered = example().render((3, 1)).to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "O.##.......");
+        assert_eq!(lines[1], "#..O#...#..");
+        assert_eq!(lines[2], ".#....X..#.");
+        assert_eq!(lines[3], "..#.#...#O#");
+        // Wrapped back round to the left.
+        assert_eq!(lines[4], ".X...##..#.");
+        assert_eq!(rendered.matches('X').count(), 7);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let parse = |lines: &[&str]| Map::parse(lines.iter().map(|l| l.to_string()));
+        assert!(parse(&["..#", ".x."]).is_err());
+        assert!(parse(&["..#", "."]).is_err());
+        assert!(parse(&[]).is_err());
+    }
+}
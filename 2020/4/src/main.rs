@@ -1,155 +1,441 @@
 use regex::Regex;
-use std::collections::HashMap;
-use std::io::{self, BufRead, Error, Lines, StdinLock};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead};
+use std::process;
 
 #[derive(Default, Debug)]
 struct Passport {
     fields: HashMap<String, String>,
 }
 
-const REQUIRED: [&'static str; 7] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
-const EYE_COLORS: [&'static str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+// What a field's value has to look like before any other rule applies.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    #[default]
+    String,
+    Number,
+}
 
-impl Passport {
-    fn valid(&self, validators: &HashMap<String, Box<dyn Fn(String) -> bool>>) -> bool {
-        for r in &REQUIRED {
-            let data = self.fields.get(&r.to_string());
-
-            match data {
-                Some(d) => {
-                    let v = validators.get(&r.to_string());
-                    match v {
-                        Some(validator) => {
-                            let res = validator(d.to_string());
-                            if !res {
-                                return false;
-                            }
-                            ()
-                        }
-                        None => (),
-                    }
-                }
-                None => {
-                    return false;
+// One field as written in the schema file.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct FieldSpec {
+    #[serde(default)]
+    required: bool,
+    #[serde(default, rename = "type")]
+    kind: Kind,
+    range: Option<(u64, u64)>,
+    // A number followed by one of these units, each with its own range.
+    #[serde(default)]
+    units: BTreeMap<String, (u64, u64)>,
+    pattern: Option<String>,
+    one_of: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct SchemaSpec {
+    fields: BTreeMap<String, FieldSpec>,
+}
+
+#[derive(Debug)]
+struct Field {
+    required: bool,
+    kind: Kind,
+    range: Option<(u64, u64)>,
+    units: BTreeMap<String, (u64, u64)>,
+    pattern: Option<Regex>,
+    one_of: Option<Vec<String>>,
+}
+
+impl Field {
+    fn from_spec(name: &str, spec: FieldSpec) -> Result<Field, Box<dyn std::error::Error>> {
+        if spec.range.is_some() && spec.kind != Kind::Number {
+            return Err(format!("{}: range needs \"type\": \"number\"", name).into());
+        }
+        if !spec.units.is_empty() && spec.kind == Kind::Number {
+            return Err(format!("{}: units can't be used with a plain number", name).into());
+        }
+        let pattern = match spec.pattern {
+            Some(p) => Some(Regex::new(&p).map_err(|e| format!("{}: {}", name, e))?),
+            None => None,
+        };
+        Ok(Field {
+            required: spec.required,
+            kind: spec.kind,
+            range: spec.range,
+            units: spec.units,
+            pattern,
+            one_of: spec.one_of,
+        })
+    }
+
+    // Stops at the first rule the value breaks, since the rest usually
+    // don't mean much once that happens.
+    fn check(&self, value: &str) -> Result<(), Rule> {
+        if self.kind == Kind::Number {
+            let n = parse_number(value).ok_or(Rule::Number)?;
+            if let Some((min, max)) = self.range {
+                if n < min || n > max {
+                    return Err(Rule::Range(min, max));
                 }
             }
         }
-        true
+
+        if !self.units.is_empty() {
+            let split = value
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(value.len());
+            let (num, unit) = value.split_at(split);
+            let (n, (min, max)) = match (parse_number(num), self.units.get(unit)) {
+                (Some(n), Some(range)) => (n, *range),
+                _ => return Err(Rule::Units(self.units.keys().cloned().collect())),
+            };
+            if n < min || n > max {
+                return Err(Rule::UnitRange(unit.to_string(), min, max));
+            }
+        }
+
+        if let Some(re) = &self.pattern {
+            if !re.is_match(value) {
+                return Err(Rule::Pattern(re.as_str().to_string()));
+            }
+        }
+
+        if let Some(values) = &self.one_of {
+            if !values.iter().any(|v| v == value) {
+                return Err(Rule::OneOf(values.clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// A schema rule a value broke.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Rule {
+    Number,
+    Range(u64, u64),
+    Units(Vec<String>),
+    UnitRange(String, u64, u64),
+    Pattern(String),
+    OneOf(Vec<String>),
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::Number => write!(f, "not a number"),
+            Rule::Range(min, max) => write!(f, "not in {}..={}", min, max),
+            Rule::Units(units) => write!(f, "not a number in {}", units.join(" or ")),
+            Rule::UnitRange(unit, min, max) => {
+                write!(f, "not in {}..={} {}", min, max, unit)
+            }
+            Rule::Pattern(p) => write!(f, "doesn't match {}", p),
+            Rule::OneOf(values) => write!(f, "not one of {}", values.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Failure {
+    Missing(String),
+    Invalid(String, Rule),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Missing(name) => write!(f, "{}: missing", name),
+            Failure::Invalid(name, rule) => write!(f, "{}: {}", name, rule),
+        }
+    }
+}
+
+fn parse_number(st: &str) -> Option<u64> {
+    if st.is_empty() || !st.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    st.parse().ok()
+}
+
+#[derive(Debug)]
+struct Schema {
+    fields: BTreeMap<String, Field>,
+}
+
+impl Schema {
+    fn parse(json: &str) -> Result<Schema, Box<dyn std::error::Error>> {
+        let spec: SchemaSpec = serde_json::from_str(json)?;
+        let fields = spec
+            .fields
+            .into_iter()
+            .map(|(name, spec)| Ok((name.clone(), Field::from_spec(&name, spec)?)))
+            .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+        Ok(Schema { fields })
+    }
+}
+
+impl Passport {
+    // Every field that's missing or breaks a rule. Fields the schema doesn't
+    // mention are allowed through unchecked.
+    fn validate(&self, schema: &Schema) -> Vec<Failure> {
+        schema
+            .fields
+            .iter()
+            .filter_map(|(name, field)| match self.fields.get(name) {
+                Some(value) => field
+                    .check(value)
+                    .err()
+                    .map(|rule| Failure::Invalid(name.clone(), rule)),
+                None if field.required => Some(Failure::Missing(name.clone())),
+                None => None,
+            })
+            .collect()
+    }
+
+    fn valid(&self, schema: &Schema) -> bool {
+        self.validate(schema).is_empty()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let sorted: BTreeMap<&String, &String> = self.fields.iter().collect();
+        serde_json::json!(sorted)
     }
 }
 
-fn parse_passports(lines: Lines<StdinLock>) -> Result<Vec<Passport>, io::Error> {
-    let re = Regex::new(r"(?P<key>\w+):(?P<value>\S+)").unwrap();
+// How many passports failed for each reason.
+fn summarize(reports: &[Vec<Failure>]) -> BTreeMap<&Failure, usize> {
+    let mut counts = BTreeMap::new();
+    for failure in reports.iter().flatten() {
+        *counts.entry(failure).or_insert(0) += 1;
+    }
+    counts
+}
 
+fn parse_passports(lines: impl Iterator<Item = String>) -> Result<Vec<Passport>, String> {
     let mut passports: Vec<Passport> = vec![];
     let mut peekable = lines.peekable();
     while peekable.peek().is_some() {
         let v: Vec<String> = peekable
             .by_ref()
-            .map(|u| u.unwrap())
-            .take_while(|line| line.ne(""))
+            .take_while(|line| !line.is_empty())
             .collect();
         let st = v.join(" ");
         let mut passport: Passport = Default::default();
 
         for field in st.split_whitespace() {
-            let caps = re.captures(field).unwrap();
-            passport
-                .fields
-                .insert(caps["key"].to_string(), caps["value"].to_string());
+            let (key, value) = field
+                .split_once(':')
+                .ok_or(format!("field without a value: {}", field))?;
+            passport.fields.insert(key.to_string(), value.to_string());
         }
 
-        passports.push(passport);
+        if !passport.fields.is_empty() {
+            passports.push(passport);
+        }
     }
     Ok(passports)
 }
 
+const USAGE: &str = "usage: aoc4 [--report | --json] [schema]";
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{}; {}", problem, USAGE);
+    process::exit(2);
+}
+
 fn main() {
+    // Optionally the path to a schema to validate against instead of the
+    // puzzle's rules, and `--report` to explain every rejection or `--json`
+    // to print the valid passports.
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if let Some(flag) = args
+        .iter()
+        .find(|a| a.starts_with("--") && !["--report", "--json"].contains(&a.as_str()))
+    {
+        usage(&format!("unknown flag {}", flag));
+    }
+    let report = args.iter().any(|a| a == "--report");
+    let json = args.iter().any(|a| a == "--json");
+    args.retain(|a| !a.starts_with("--"));
+
+    let schema = match args.first() {
+        Some(path) => Schema::parse(&fs::read_to_string(path).unwrap()).unwrap(),
+        None => Schema::parse(include_str!("../schema.json")).unwrap(),
+    };
+
     let stdin = io::stdin();
-    let lines = stdin.lock().lines();
+    let lines = stdin.lock().lines().map(|line| line.unwrap());
     let passports = parse_passports(lines).unwrap();
 
-    let mut validators: HashMap<String, Box<dyn Fn(String) -> bool>> = HashMap::new();
-
-    validators.insert(
-        "byr".to_string(),
-        Box::new(|st: String| {
-            let parsed = st.parse::<u32>().unwrap();
-            parsed >= 1920 && parsed <= 2002
-        }),
-    );
-
-    validators.insert(
-        "iyr".to_string(),
-        Box::new(|st: String| {
-            let parsed = st.parse::<u32>().unwrap();
-            parsed >= 2010 && parsed <= 2020
-        }),
-    );
-
-    validators.insert(
-        "eyr".to_string(),
-        Box::new(|st: String| {
-            let parsed = st.parse::<u32>().unwrap();
-            parsed >= 2020 && parsed <= 2030
-        }),
-    );
-
-    validators.insert(
-        "hgt".to_string(),
-        Box::new(|st: String| {
-            let height_re = Regex::new(r"(?P<num>\d+)(?P<unit>in|cm)").unwrap();
-            let matched = height_re.captures(st.as_str());
-            match matched {
-                Some(caps) => {
-                    let parsed_num = caps["num"].parse::<u32>().unwrap();
-                    match &caps["unit"] {
-                        "cm" => parsed_num >= 150 && parsed_num <= 193,
-                        "in" => parsed_num >= 59 && parsed_num <= 76,
-                        _ => false,
+    if json {
+        let valid: Vec<serde_json::Value> = passports
+            .iter()
+            .filter(|p| p.valid(&schema))
+            .map(|p| p.to_json())
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&valid).unwrap());
+        return;
+    }
+
+    let reports: Vec<Vec<Failure>> = passports.iter().map(|p| p.validate(&schema)).collect();
+
+    if report {
+        for (i, failures) in reports.iter().enumerate() {
+            if failures.is_empty() {
+                println!("passport {}: valid", i + 1);
+                continue;
+            }
+            println!("passport {}: invalid", i + 1);
+            for failure in failures {
+                match failure {
+                    Failure::Invalid(name, _) => {
+                        println!("  {} ({:?})", failure, passports[i].fields[name])
                     }
+                    Failure::Missing(_) => println!("  {}", failure),
                 }
-                None => false,
-            }
-        }),
-    );
-
-    validators.insert(
-        "hcl".to_string(),
-        Box::new(|st: String| {
-            let hcl_re = Regex::new(r"#(?P<hcl>[0-9a-f]+)").unwrap();
-            let matched = hcl_re.captures(st.as_str());
-
-            match matched {
-                Some(caps) => caps["hcl"].len() == 6,
-                None => false,
-            }
-        }),
-    );
-
-    validators.insert(
-        "ecl".to_string(),
-        Box::new(|st: String| EYE_COLORS.contains(&st.as_str())),
-    );
-
-    validators.insert(
-        "pid".to_string(),
-        Box::new(|st: String| {
-            let pid_re = Regex::new(r"(?P<pid>\d+)").unwrap();
-            let matched = pid_re.captures(st.as_str());
-
-            match matched {
-                Some(caps) => caps["pid"].len() == 9,
-                None => false,
             }
-        }),
-    );
-
-    let mut valid = 0;
-    for passport in passports {
-        if passport.valid(&validators) {
-            valid += 1;
+        }
+        println!();
+        for (failure, count) in summarize(&reports) {
+            println!("{:>5} {}", count, failure);
         }
     }
+
+    let valid = reports.iter().filter(|r| r.is_empty()).count();
     println!("{:?}", valid)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::parse(include_str!("../schema.json")).unwrap()
+    }
+
+    fn example() -> Vec<Passport> {
+        parse_passports(include_str!("../tiny_input").lines().map(|l| l.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_example() {
+        let schema = schema();
+        let valid: Vec<bool> = example().iter().map(|p| p.valid(&schema)).collect();
+        assert_eq!(
+            valid,
+            vec![false, false, false, false, true, true, true, true]
+        );
+    }
+
+    #[test]
+    fn test_fields() {
+        let schema = schema();
+        let check = |name: &str, value: &str| schema.fields[name].check(value).is_ok();
+
+        assert!(check("byr", "2002"));
+        assert!(!check("byr", "2003"));
+        assert!(!check("byr", "+2002"));
+        assert!(check("hgt", "60in"));
+        assert!(check("hgt", "190cm"));
+        assert!(!check("hgt", "190in"));
+        assert!(!check("hgt", "190"));
+        assert!(check("hcl", "#123abc"));
+        assert!(!check("hcl", "#123abz"));
+        assert!(!check("hcl", "123abc"));
+        assert!(check("ecl", "brn"));
+        assert!(!check("ecl", "wat"));
+        assert!(check("pid", "000000001"));
+        assert!(!check("pid", "0123456789"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = schema();
+        let passports = example();
+
+        assert_eq!(
+            passports[0].validate(&schema),
+            vec![
+                Failure::Invalid("eyr".to_string(), Rule::Range(2020, 2030)),
+                Failure::Invalid(
+                    "hgt".to_string(),
+                    Rule::Units(vec!["cm".to_string(), "in".to_string()])
+                ),
+                Failure::Invalid("pid".to_string(), Rule::Pattern("^[0-9]{9}$".to_string())),
+            ]
+        );
+        // Every field is present but wrong.
+        assert_eq!(passports[3].validate(&schema).len(), 7);
+        assert!(passports[4].validate(&schema).is_empty());
+
+        let mut missing = Passport::default();
+        missing
+            .fields
+            .insert("hgt".to_string(), "200cm".to_string());
+        let failures = missing.validate(&schema);
+        assert_eq!(failures.len(), 7);
+        assert!(failures.contains(&Failure::Missing("byr".to_string())));
+        assert!(failures.contains(&Failure::Invalid(
+            "hgt".to_string(),
+            Rule::UnitRange("cm".to_string(), 150, 193)
+        )));
+        assert_eq!(failures[0].to_string(), "byr: missing");
+    }
+
+    #[test]
+    fn test_summarize() {
+        let schema = schema();
+        let reports: Vec<Vec<Failure>> = example().iter().map(|p| p.validate(&schema)).collect();
+        let counts = summarize(&reports);
+        assert_eq!(
+            counts[&Failure::Invalid("eyr".to_string(), Rule::Range(2020, 2030))],
+            3
+        );
+        assert_eq!(
+            counts.values().sum::<usize>(),
+            reports.iter().flatten().count()
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let passports = example();
+        assert_eq!(
+            passports[7].to_json().to_string(),
+            r##"{"byr":"1944","ecl":"blu","eyr":"2021","hcl":"#b6652a","hgt":"158cm","iyr":"2010","pid":"093154719"}"##
+        );
+    }
+
+    #[test]
+    fn test_custom_schema() {
+        let schema = Schema::parse(r#"{ "fields": { "cid": { "required": true } } }"#).unwrap();
+        let valid: Vec<bool> = example().iter().map(|p| p.valid(&schema)).collect();
+        assert_eq!(
+            valid,
+            vec![true, false, true, false, false, true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_bad_schema() {
+        for json in [
+            r#"{ "fields": { "byr": { "range": [1, 2] } } }"#,
+            r#"{ "fields": { "byr": { "type": "number", "units": { "cm": [1, 2] } } } }"#,
+            r#"{ "fields": { "byr": { "pattern": "(" } } }"#,
+            r#"{ "fields": { "byr": { "type": "date" } } }"#,
+            r#"{ "fields": { "byr": { "requird": true } } }"#,
+        ]
+        .iter()
+        {
+            assert!(Schema::parse(json).is_err(), "{}", json);
+        }
+    }
+}
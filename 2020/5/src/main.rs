@@ -1,18 +1,14 @@
 use std::cmp::Ordering;
-use std::io::{self, BufRead, Error, Lines, StdinLock};
+use std::fmt;
+use std::io::{self, BufRead};
+use std::process;
 
-#[derive(Default, Debug, Eq, PartialEq)]
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
 struct Seat {
     row: u32,
     col: u32,
 }
 
-impl Seat {
-    fn id(&self) -> u32 {
-        self.row * 8 + self.col
-    }
-}
-
 impl Ord for Seat {
     fn cmp(&self, other: &Self) -> Ordering {
         self.row.cmp(&other.row).then(self.col.cmp(&other.col))
@@ -21,53 +17,306 @@ impl Ord for Seat {
 
 impl PartialOrd for Seat {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.row.cmp(&other.row).then(self.col.cmp(&other.col)))
+        Some(self.cmp(other))
     }
 }
 
-fn bin_search(
-    code: &mut dyn Iterator<Item = char>,
-    range: (u32, u32),
-    sigils: (char, char),
-) -> u32 {
-    let mut bottom = range.0;
-    let mut top = range.1;
+// How many characters encode the row and the column, and which pair of
+// characters means the lower and upper half at each step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Geometry {
+    row_bits: u32,
+    col_bits: u32,
+    row_sigils: (char, char),
+    col_sigils: (char, char),
+}
 
-    for c in code {
-        match c {
-            k if k == sigils.0 => top -= (top - bottom) / 2 + 1,
-            k if k == sigils.1 => bottom += (top - bottom - 1) / 2 + 1,
-            _ => panic!("unexpected arg: {}", c),
+// Enough for any real plane while keeping `Cabin`'s one flag per seat small.
+const MAX_BITS: u32 = 24;
+
+const PLANE: Geometry = Geometry {
+    row_bits: 7,
+    col_bits: 3,
+    row_sigils: ('F', 'B'),
+    col_sigils: ('L', 'R'),
+};
+
+impl Geometry {
+    fn new(
+        row_bits: u32,
+        col_bits: u32,
+        row_sigils: (char, char),
+        col_sigils: (char, char),
+    ) -> Result<Geometry, String> {
+        if row_bits > MAX_BITS || col_bits > MAX_BITS || row_bits + col_bits > MAX_BITS {
+            return Err(format!(
+                "too many bits: {}+{}, at most {}",
+                row_bits, col_bits, MAX_BITS
+            ));
+        }
+        if row_sigils.0 == row_sigils.1 || col_sigils.0 == col_sigils.1 {
+            return Err("lower and upper sigils must differ".to_string());
         }
-        //println!("bottom: {}, top: {}", bottom, top)
+        Ok(Geometry {
+            row_bits,
+            col_bits,
+            row_sigils,
+            col_sigils,
+        })
+    }
+
+    fn rows(&self) -> u32 {
+        1 << self.row_bits
     }
 
-    assert_eq!(bottom, top);
-    bottom
+    fn cols(&self) -> u32 {
+        1 << self.col_bits
+    }
+
+    fn id(&self, seat: &Seat) -> u32 {
+        seat.row * self.cols() + seat.col
+    }
+
+    fn seat(&self, id: u32) -> Seat {
+        Seat {
+            row: id / self.cols(),
+            col: id % self.cols(),
+        }
+    }
+
+    fn decode(&self, code: &str) -> Result<Seat, String> {
+        let len = code.chars().count();
+        if len != (self.row_bits + self.col_bits) as usize {
+            return Err(format!(
+                "{:?} is {} long, expected {}",
+                code,
+                len,
+                self.row_bits + self.col_bits
+            ));
+        }
+
+        let mut chars = code.chars();
+        Ok(Seat {
+            row: decode_half(chars.by_ref().take(self.row_bits as usize), self.row_sigils)?,
+            col: decode_half(chars, self.col_sigils)?,
+        })
+    }
+
+    fn encode(&self, seat: &Seat) -> Result<String, String> {
+        if seat.row >= self.rows() || seat.col >= self.cols() {
+            return Err(format!(
+                "{:?} is outside a {}x{} plane",
+                seat,
+                self.rows(),
+                self.cols()
+            ));
+        }
+
+        let mut code = encode_half(seat.row, self.row_bits, self.row_sigils);
+        code.push_str(&encode_half(seat.col, self.col_bits, self.col_sigils));
+        Ok(code)
+    }
 }
 
-fn parse_seat(st: String) -> Seat {
-    let mut seat: Seat = Default::default();
+// Each character halves the remaining range, which is just reading it as a
+// binary number with the upper sigil as 1.
+fn decode_half(mut code: impl Iterator<Item = char>, sigils: (char, char)) -> Result<u32, String> {
+    code.try_fold(0, |acc, c| match c {
+        k if k == sigils.0 => Ok(acc << 1),
+        k if k == sigils.1 => Ok(acc << 1 | 1),
+        _ => Err(format!(
+            "unexpected {:?}, expected {:?} or {:?}",
+            c, sigils.0, sigils.1
+        )),
+    })
+}
 
-    let mut iter = st.chars();
-    let mut row_code = iter.by_ref().take(7);
+fn encode_half(n: u32, bits: u32, sigils: (char, char)) -> String {
+    (0..bits)
+        .rev()
+        .map(|i| if n >> i & 1 == 0 { sigils.0 } else { sigils.1 })
+        .collect()
+}
 
-    seat.row = bin_search(&mut row_code, (0, 128), ('F', 'B'));
+// Every seat on the plane by id. Seats before the first occupied one and
+// after the last are taken not to exist at all.
+#[derive(Debug)]
+struct Cabin {
+    geometry: Geometry,
+    occupied: Vec<bool>,
+    first: u32,
+    last: u32,
+}
 
-    let mut col_code = iter.by_ref().take(3);
-    seat.col = bin_search(&mut col_code, (0, 7), ('L', 'R'));
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Place {
+    Occupied,
+    Empty,
+    Missing,
+}
 
-    seat
+// A run of empty seats, by id. It's `isolated` when it's a single seat with
+// both neighbours occupied, which is where part 2's seat is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Gap {
+    start: u32,
+    len: u32,
+    isolated: bool,
 }
 
-fn find_seats(lines: Lines<StdinLock>) -> Result<Vec<Seat>, io::Error> {
-    let seats = lines
-        .map(|line| {
-            let li = line.unwrap();
-            parse_seat(li)
-        })
-        .collect();
-    Ok(seats)
+impl Cabin {
+    fn new(geometry: Geometry, seats: &[Seat]) -> Cabin {
+        let mut occupied = vec![false; (geometry.rows() * geometry.cols()) as usize];
+        for seat in seats {
+            occupied[geometry.id(seat) as usize] = true;
+        }
+        // With nobody aboard, first > last and every seat is missing.
+        let first = occupied.iter().position(|o| *o).unwrap_or(occupied.len()) as u32;
+        let last = occupied.iter().rposition(|o| *o).unwrap_or(0) as u32;
+        Cabin {
+            geometry,
+            occupied,
+            first,
+            last,
+        }
+    }
+
+    fn place(&self, id: u32) -> Place {
+        if id < self.first || id > self.last {
+            Place::Missing
+        } else if self.occupied[id as usize] {
+            Place::Occupied
+        } else {
+            Place::Empty
+        }
+    }
+
+    fn gaps(&self) -> Vec<Gap> {
+        let mut gaps: Vec<Gap> = vec![];
+        for id in self.first..=self.last {
+            if self.place(id) != Place::Empty {
+                continue;
+            }
+            match gaps.last_mut() {
+                Some(gap) if gap.start + gap.len == id => gap.len += 1,
+                _ => gaps.push(Gap {
+                    start: id,
+                    len: 1,
+                    isolated: false,
+                }),
+            }
+        }
+        // The first and last seats are occupied, so every run has occupied
+        // seats either side of it.
+        for gap in &mut gaps {
+            gap.isolated = gap.len == 1;
+        }
+        gaps
+    }
+}
+
+impl fmt::Display for Cabin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let isolated: Vec<u32> = self
+            .gaps()
+            .iter()
+            .filter(|gap| gap.isolated)
+            .map(|gap| gap.start)
+            .collect();
+        for row in 0..self.geometry.rows() {
+            write!(f, "{:>4} ", row)?;
+            for col in 0..self.geometry.cols() {
+                let id = self.geometry.id(&Seat { row, col });
+                let ch = match self.place(id) {
+                    Place::Occupied => '#',
+                    Place::Empty if isolated.contains(&id) => '*',
+                    Place::Empty => '.',
+                    Place::Missing => ' ',
+                };
+                write!(f, "{}", ch)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn find_seats(
+    lines: impl Iterator<Item = String>,
+    geometry: &Geometry,
+) -> Result<Vec<Seat>, String> {
+    lines
+        .filter(|line| !line.is_empty())
+        .map(|line| geometry.decode(&line))
+        .collect()
+}
+
+const USAGE: &str = "usage: aoc5 [--map] [<row bits> <col bits> <row sigils> <col sigils>]";
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{}; {}", problem, USAGE);
+    process::exit(2);
+}
+
+fn bits(st: &str) -> u32 {
+    st.parse()
+        .unwrap_or_else(|_| usage(&format!("expected a number of bits, got {:?}", st)))
+}
+
+fn sigils(st: &str) -> (char, char) {
+    let chars: Vec<char> = st.chars().collect();
+    match chars[..] {
+        [lower, upper] => (lower, upper),
+        _ => usage(&format!("expected two sigils, got {:?}", st)),
+    }
+}
+
+fn main() {
+    // Optionally `<row bits> <col bits> <row sigils> <col sigils>`, like
+    // `7 3 FB LR` for the puzzle's plane, and `--map` to draw the cabin and
+    // list its gaps.
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(flag) = args.iter().find(|a| a.starts_with("--") && *a != "--map") {
+        usage(&format!("unknown flag {}", flag));
+    }
+    let map = args.iter().any(|a| a == "--map");
+    args.retain(|a| !a.starts_with("--"));
+    let geometry = match &args[..] {
+        [] => PLANE,
+        [row_bits, col_bits, row_sigils, col_sigils] => Geometry::new(
+            bits(row_bits),
+            bits(col_bits),
+            sigils(row_sigils),
+            sigils(col_sigils),
+        )
+        .unwrap_or_else(|e| usage(&e)),
+        _ => usage("expected four geometry arguments"),
+    };
+
+    let stdin = io::stdin();
+    let lines = stdin.lock().lines().map(|line| line.unwrap());
+    let seats = find_seats(lines, &geometry).unwrap();
+    let cabin = Cabin::new(geometry, &seats);
+
+    if map {
+        print!("{}", cabin);
+        for gap in cabin.gaps() {
+            println!(
+                "{:?}: {} empty{}",
+                geometry.seat(gap.start),
+                gap.len,
+                if gap.isolated { ", isolated" } else { "" }
+            );
+        }
+    }
+
+    let max = seats.iter().map(|seat| geometry.id(seat)).max().unwrap();
+    println!("{}", max);
+
+    for gap in cabin.gaps().iter().filter(|gap| gap.isolated) {
+        let mine = geometry.seat(gap.start);
+        println!("{} {}", gap.start, geometry.encode(&mine).unwrap())
+    }
 }
 
 #[cfg(test)]
@@ -76,65 +325,159 @@ mod tests {
 
     #[test]
     fn test_parse_seat() {
-        let seat = parse_seat("FBFBBFFRLR".to_string());
+        let seat = PLANE.decode("FBFBBFFRLR").unwrap();
         assert_eq!(seat.row, 44);
         assert_eq!(seat.col, 5);
-        assert_eq!(seat.id(), 357);
+        assert_eq!(PLANE.id(&seat), 357);
 
-        let seat = parse_seat("BFFFBBFRRR".to_string());
+        let seat = PLANE.decode("BFFFBBFRRR").unwrap();
         assert_eq!(seat.row, 70);
         assert_eq!(seat.col, 7);
-        assert_eq!(seat.id(), 567);
+        assert_eq!(PLANE.id(&seat), 567);
 
-        let seat = parse_seat("FFFBBBFRRR".to_string());
+        let seat = PLANE.decode("FFFBBBFRRR").unwrap();
         assert_eq!(seat.row, 14);
         assert_eq!(seat.col, 7);
-        assert_eq!(seat.id(), 119);
+        assert_eq!(PLANE.id(&seat), 119);
 
-        let seat = parse_seat("BBFFBBFRLL".to_string());
+        let seat = PLANE.decode("BBFFBBFRLL").unwrap();
         assert_eq!(seat.row, 102);
         assert_eq!(seat.col, 4);
-        assert_eq!(seat.id(), 820);
+        assert_eq!(PLANE.id(&seat), 820);
     }
-}
 
-fn main() {
-    let stdin = io::stdin();
-    let lines = stdin.lock().lines();
-    let mut seats = find_seats(lines).unwrap();
-
-    let mut max = 0;
-    for seat in &seats {
-        let seat_id = seat.id();
-        if seat_id > max {
-            max = seat_id;
-        }
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            PLANE.encode(&Seat { row: 44, col: 5 }).unwrap(),
+            "FBFBBFFRLR"
+        );
+        assert!(PLANE.encode(&Seat { row: 128, col: 0 }).is_err());
+        assert!(PLANE.encode(&Seat { row: 0, col: 8 }).is_err());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(PLANE.decode("FBFBBFFRL").is_err());
+        assert!(PLANE.decode("FBFBBFFRLRR").is_err());
+        assert!(PLANE.decode("FBFBBFFRLX").is_err());
+        // Column sigils in the row part.
+        assert!(PLANE.decode("FBFBBFLRLR").is_err());
+        assert!(PLANE.decode("").is_err());
     }
-    println!("{}", max);
 
-    seats.sort();
+    #[test]
+    fn test_bad_geometry() {
+        assert!(Geometry::new(16, 16, ('F', 'B'), ('L', 'R')).is_err());
+        assert!(Geometry::new(20, 5, ('F', 'B'), ('L', 'R')).is_err());
+        assert!(Geometry::new(u32::MAX, 1, ('F', 'B'), ('L', 'R')).is_err());
+        assert!(Geometry::new(1, u32::MAX, ('F', 'B'), ('L', 'R')).is_err());
+        assert!(Geometry::new(20, 4, ('F', 'B'), ('L', 'R')).is_ok());
+        assert!(Geometry::new(7, 3, ('F', 'F'), ('L', 'R')).is_err());
+        assert!(Geometry::new(0, 0, ('F', 'B'), ('L', 'R')).is_ok());
+    }
 
-    let mut cur_row = seats[0].row;
-    let mut cur_col = seats[0].col - 1;
+    fn geometries() -> Vec<Geometry> {
+        vec![
+            PLANE,
+            Geometry::new(1, 1, ('0', '1'), ('0', '1')).unwrap(),
+            Geometry::new(4, 0, ('a', 'b'), ('c', 'd')).unwrap(),
+            Geometry::new(0, 5, ('↑', '↓'), ('←', '→')).unwrap(),
+            Geometry::new(9, 2, ('B', 'F'), ('R', 'L')).unwrap(),
+        ]
+    }
 
-    for seat in &seats {
-        if seat.col == cur_col + 1 {
-            cur_col = seat.col;
-        } else if seat.row == cur_row + 1 {
-            // Next row, reset
-            cur_row = seat.row;
-            cur_col = seat.col;
-        } else {
-            break;
+    #[test]
+    fn test_round_trip_seats() {
+        // Every seat survives encoding and decoding.
+        for geometry in geometries() {
+            for row in 0..geometry.rows() {
+                for col in 0..geometry.cols() {
+                    let seat = Seat { row, col };
+                    let code = geometry.encode(&seat).unwrap();
+                    assert_eq!(geometry.decode(&code), Ok(seat), "{:?}", geometry);
+                }
+            }
         }
     }
 
-    println!(
-        "{}",
-        Seat {
-            row: cur_row,
-            col: cur_col + 1
+    #[test]
+    fn test_round_trip_codes() {
+        // Every well-formed code survives decoding and encoding. The codes are
+        // built straight from the bits of every number that fits, first
+        // character highest, rather than with `encode`.
+        for geometry in geometries() {
+            let sigils: Vec<(char, char)> = (0..geometry.row_bits)
+                .map(|_| geometry.row_sigils)
+                .chain((0..geometry.col_bits).map(|_| geometry.col_sigils))
+                .collect();
+            for n in 0..1u32 << sigils.len() {
+                let code: String = sigils
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| {
+                        if n >> (sigils.len() - 1 - i) & 1 == 0 {
+                            s.0
+                        } else {
+                            s.1
+                        }
+                    })
+                    .collect();
+                let seat = geometry.decode(&code).unwrap();
+                assert_eq!(geometry.encode(&seat).unwrap(), code);
+            }
         }
-        .id()
-    )
+    }
+
+    fn tiny_cabin(codes: &[&str]) -> Cabin {
+        let geometry = Geometry::new(2, 2, ('F', 'B'), ('L', 'R')).unwrap();
+        let seats: Vec<Seat> = codes.iter().map(|c| geometry.decode(c).unwrap()).collect();
+        Cabin::new(geometry, &seats)
+    }
+
+    #[test]
+    fn test_gaps() {
+        // Ids 1, 2, 4, 7, 8 and 10 are taken.
+        let cabin = tiny_cabin(&["FFLR", "FFRL", "FBLL", "FBRR", "BFLL", "BFRL"]);
+        assert_eq!(cabin.place(0), Place::Missing);
+        assert_eq!(cabin.place(1), Place::Occupied);
+        assert_eq!(cabin.place(3), Place::Empty);
+        assert_eq!(cabin.place(11), Place::Missing);
+        assert_eq!(
+            cabin.gaps(),
+            vec![
+                Gap {
+                    start: 3,
+                    len: 1,
+                    isolated: true
+                },
+                Gap {
+                    start: 5,
+                    len: 2,
+                    isolated: false
+                },
+                Gap {
+                    start: 9,
+                    len: 1,
+                    isolated: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let cabin = tiny_cabin(&["FFLR", "FFRL", "FBLL", "FBRR", "BFLL", "BFRL"]);
+        assert_eq!(
+            cabin.to_string(),
+            "   0  ##*\n   1 #..#\n   2 #*# \n   3     \n"
+        );
+    }
+
+    #[test]
+    fn test_no_gaps() {
+        let cabin = tiny_cabin(&["FFRR", "FBLL"]);
+        assert!(cabin.gaps().is_empty());
+        assert!(tiny_cabin(&[]).gaps().is_empty());
+    }
 }
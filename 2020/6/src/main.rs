@@ -1,69 +1,235 @@
-use std::collections::{HashMap, HashSet};
-use std::io::{self, BufRead, Error, Lines, StdinLock};
+use std::env;
+use std::io::{self, BufRead};
+use std::process;
 
+const QUESTIONS: u32 = 26;
+const ALL: u32 = (1 << QUESTIONS) - 1;
+
+// Each person's answers as a bitset, with question `a` in the lowest bit.
 #[derive(Default, Debug)]
 struct Group {
-    questions_answered: Vec<HashSet<char>>,
+    people: Vec<u32>,
 }
 
 impl Group {
-    fn count_by_question(&self) -> HashMap<char, u32> {
-        let mut hm = HashMap::new();
-        for person in &self.questions_answered {
-            for q in person {
-                *hm.entry(*q).or_insert(0) += 1;
-            }
-        }
-        hm
+    fn parse_person(st: &str) -> Result<u32, String> {
+        st.chars().try_fold(0, |acc, c| match c {
+            'a'..='z' => Ok(acc | 1 << (c as u32 - 'a' as u32)),
+            _ => Err(format!("unexpected question: {:?}", c)),
+        })
     }
 
     fn anyone_answered_yes(&self) -> u32 {
-        self.count_by_question().len() as u32
+        self.select(Query::Anyone).count_ones()
     }
 
     fn everyone_answered_yes(&self) -> u32 {
+        self.select(Query::Everyone).count_ones()
+    }
+
+    // How many people answered yes to each question.
+    fn count_by_question(&self) -> [u32; QUESTIONS as usize] {
+        let mut counts = [0; QUESTIONS as usize];
+        for person in &self.people {
+            let mut bits = *person;
+            while bits != 0 {
+                counts[bits.trailing_zeros() as usize] += 1;
+                bits &= bits - 1;
+            }
+        }
+        counts
+    }
+
+    // The questions matching `query`, as a bitset.
+    fn select(&self, query: Query) -> u32 {
+        match query {
+            Query::Anyone => self.people.iter().fold(0, |acc, p| acc | p),
+            Query::Everyone => self.people.iter().fold(ALL, |acc, p| acc & p),
+            Query::Nobody => !self.select(Query::Anyone) & ALL,
+            Query::Exactly(k) => self.select_by_count(|n| n == k),
+            Query::AtLeast(k) => self.select_by_count(|n| n >= k),
+            Query::AtMost(k) => self.select_by_count(|n| n <= k),
+        }
+    }
+
+    fn select_by_count(&self, pred: impl Fn(u32) -> bool) -> u32 {
         self.count_by_question()
             .iter()
-            .filter(|(_, c)| **c == self.questions_answered.len() as u32)
-            .count() as u32
+            .enumerate()
+            .filter(|(_, n)| pred(**n))
+            .fold(0, |acc, (q, _)| acc | 1 << q)
     }
 }
 
-fn count_questions(lines: Lines<StdinLock>) -> Result<Vec<Group>, io::Error> {
-    let unwrapped = lines.map(|li| li.unwrap());
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Query {
+    Anyone,
+    Everyone,
+    Nobody,
+    Exactly(u32),
+    AtLeast(u32),
+    AtMost(u32),
+}
 
+impl Query {
+    fn parse(st: &str) -> Result<Query, String> {
+        let (name, k) = match st.split_once(':') {
+            Some((name, k)) => (
+                name,
+                Some(k.parse::<u32>().map_err(|e| format!("{}: {}", st, e))?),
+            ),
+            None => (st, None),
+        };
+        match (name, k) {
+            ("anyone", None) => Ok(Query::Anyone),
+            ("everyone", None) => Ok(Query::Everyone),
+            ("nobody", None) => Ok(Query::Nobody),
+            ("exactly", Some(k)) => Ok(Query::Exactly(k)),
+            ("at-least", Some(k)) => Ok(Query::AtLeast(k)),
+            ("at-most", Some(k)) => Ok(Query::AtMost(k)),
+            _ => Err(format!("unknown query: {}", st)),
+        }
+    }
+
+    // Total matching questions summed over every group.
+    fn total(self, groups: &[Group]) -> u64 {
+        groups
+            .iter()
+            .map(|g| g.select(self).count_ones() as u64)
+            .sum()
+    }
+
+    // For each question, how many groups it matched in.
+    fn histogram(self, groups: &[Group]) -> [u64; QUESTIONS as usize] {
+        let mut hist = [0; QUESTIONS as usize];
+        for group in groups {
+            let selected = group.select(self);
+            for (q, n) in hist.iter_mut().enumerate() {
+                *n += (selected >> q & 1) as u64;
+            }
+        }
+        hist
+    }
+}
+
+fn count_questions(lines: impl Iterator<Item = String>) -> Result<Vec<Group>, String> {
     let mut cur_group: Group = Default::default();
     let mut groups: Vec<Group> = vec![];
 
-    for li in unwrapped {
-        if li == "" {
-            groups.push(cur_group);
+    for li in lines {
+        if li.is_empty() {
+            if !cur_group.people.is_empty() {
+                groups.push(cur_group);
+            }
             cur_group = Default::default();
         } else {
-            let mut hs: HashSet<char> = HashSet::new();
-            for c in li.chars() {
-                hs.insert(c);
-            }
-            cur_group.questions_answered.push(hs);
+            cur_group.people.push(Group::parse_person(&li)?);
         }
     }
 
-    groups.push(cur_group);
+    if !cur_group.people.is_empty() {
+        groups.push(cur_group);
+    }
     Ok(groups)
 }
 
+const USAGE: &str = "usage: aoc6 [--histogram] [query...]";
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{}; {}", problem, USAGE);
+    process::exit(2);
+}
+
 fn main() {
-    let stdin = io::stdin();
-    let lines = stdin.lock().lines();
+    // Optionally the queries to run, like `exactly:2` or `nobody`, and
+    // `--histogram` to break each one down by question.
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if let Some(flag) = args
+        .iter()
+        .find(|a| a.starts_with("--") && *a != "--histogram")
+    {
+        usage(&format!("unknown flag {}", flag));
+    }
+    let histogram = args.iter().any(|a| a == "--histogram");
+    args.retain(|a| !a.starts_with("--"));
 
+    let stdin = io::stdin();
+    let lines = stdin.lock().lines().map(|li| li.unwrap());
     let groups = count_questions(lines).unwrap();
-    let mut part_one = 0;
-    let mut part_two = 0;
 
-    for group in groups {
-        part_one += group.anyone_answered_yes();
-        part_two += group.everyone_answered_yes();
+    if args.is_empty() {
+        let part_one: u32 = groups.iter().map(|g| g.anyone_answered_yes()).sum();
+        let part_two: u32 = groups.iter().map(|g| g.everyone_answered_yes()).sum();
+        println!("{}", part_one);
+        println!("{}", part_two);
+        return;
+    }
+
+    for arg in &args {
+        let query = Query::parse(arg).unwrap_or_else(|e| usage(&e));
+        println!("{}: {}", arg, query.total(&groups));
+        if histogram {
+            for (q, n) in query.histogram(&groups).iter().enumerate() {
+                println!("  {} {}", (b'a' + q as u8) as char, n);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Group> {
+        count_questions(include_str!("../tiny_input").lines().map(|l| l.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_example() {
+        let groups = example();
+        assert_eq!(groups.len(), 5);
+        let anyone: Vec<u32> = groups.iter().map(|g| g.anyone_answered_yes()).collect();
+        let everyone: Vec<u32> = groups.iter().map(|g| g.everyone_answered_yes()).collect();
+        assert_eq!(anyone, vec![3, 3, 3, 1, 1]);
+        assert_eq!(everyone, vec![3, 0, 1, 1, 1]);
+        assert_eq!(Query::Anyone.total(&groups), 11);
+        assert_eq!(Query::Everyone.total(&groups), 6);
+    }
+
+    #[test]
+    fn test_select() {
+        let groups = example();
+        // ab, ac
+        let g = &groups[2];
+        assert_eq!(g.select(Query::Exactly(1)), 0b110);
+        assert_eq!(g.select(Query::Exactly(2)), 0b1);
+        assert_eq!(g.select(Query::AtLeast(1)), 0b111);
+        assert_eq!(g.select(Query::AtMost(1)), ALL & !0b1);
+        assert_eq!(g.select(Query::Nobody).count_ones(), 23);
+        assert_eq!(g.select(Query::Exactly(0)), g.select(Query::Nobody));
+        assert_eq!(
+            g.select(Query::AtLeast(g.people.len() as u32)),
+            g.select(Query::Everyone)
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let groups = example();
+        let hist = Query::Anyone.histogram(&groups);
+        assert_eq!(&hist[..4], &[4, 4, 3, 0]);
+        assert_eq!(hist.iter().sum::<u64>(), 11);
+        assert_eq!(Query::Everyone.histogram(&groups)[0], 3);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Query::parse("nobody"), Ok(Query::Nobody));
+        assert_eq!(Query::parse("at-least:2"), Ok(Query::AtLeast(2)));
+        assert_eq!(Query::parse("exactly:0"), Ok(Query::Exactly(0)));
+        assert!(Query::parse("exactly").is_err());
+        assert!(Query::parse("anyone:1").is_err());
+        assert!(Query::parse("at-most:x").is_err());
+        assert!(Group::parse_person("abC").is_err());
     }
-    println!("{}", part_one);
-    println!("{}", part_two);
 }
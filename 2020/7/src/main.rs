@@ -1,137 +1,552 @@
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use std::io::{self, BufRead, Error, Lines, StdinLock};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::env;
+use std::io::{self, BufRead};
+use std::process;
 
-use std::cmp;
+#[derive(Default, Debug, Clone, PartialEq)]
+struct Rule(u32, String);
 
 #[derive(Default, Debug)]
-struct Bags {
-    bag_types: HashMap<String, Vec<Rule>>,
+struct BagRules {
+    bag: String,
+    contents: Vec<Rule>,
 }
 
-#[derive(Default, Debug)]
-struct Bag {
-    name: String,
-    contents: Vec<(u32, Bag)>,
+// The rules as a graph, with an edge weighted by count from each bag to
+// every bag it directly contains, and the same edges reversed.
+#[derive(Debug)]
+struct BagGraph {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    contains: Vec<Vec<(u32, usize)>>,
+    contained_by: Vec<Vec<usize>>,
+    // How many bags each bag holds in total, worked out once up front.
+    inside: Vec<u64>,
+    // How many levels of bags each bag holds, along its longest chain.
+    levels: Vec<usize>,
 }
 
-impl Bag {
-    fn find(&self, name: &String) -> bool {
-        if self.name.eq(name) {
-            return true;
+impl BagGraph {
+    fn new(rules: &[BagRules]) -> Result<BagGraph, String> {
+        let mut graph = BagGraph {
+            names: vec![],
+            index: HashMap::new(),
+            contains: vec![],
+            contained_by: vec![],
+            inside: vec![],
+            levels: vec![],
+        };
+
+        let mut defined = HashSet::new();
+        for rule in rules {
+            let bag = graph.node(&rule.bag);
+            if !defined.insert(bag) {
+                return Err(format!("more than one rule for {}", rule.bag));
+            }
+            for Rule(count, inner) in &rule.contents {
+                let inner = graph.node(inner);
+                graph.contains[bag].push((*count, inner));
+                graph.contained_by[inner].push(bag);
+            }
+        }
+
+        // Visiting children before parents lets each bag's total be built
+        // from totals that are already known.
+        let mut order = vec![];
+        let mut state = vec![Visit::New; graph.names.len()];
+        let mut path = vec![];
+        for bag in 0..graph.names.len() {
+            graph.visit(bag, &mut state, &mut path, &mut order)?;
         }
 
-        for (_, bag) in &self.contents {
-            if bag.find(name) {
-                return true;
+        graph.inside = vec![0; graph.names.len()];
+        graph.levels = vec![0; graph.names.len()];
+        for bag in order {
+            graph.inside[bag] = graph.contains[bag]
+                .iter()
+                .map(|(count, inner)| *count as u64 * (1 + graph.inside[*inner]))
+                .sum();
+            graph.levels[bag] = graph.contains[bag]
+                .iter()
+                .map(|(_, inner)| 1 + graph.levels[*inner])
+                .max()
+                .unwrap_or(0);
+        }
+
+        Ok(graph)
+    }
+
+    fn node(&mut self, name: &str) -> usize {
+        if let Some(i) = self.index.get(name) {
+            return *i;
+        }
+        self.names.push(name.to_string());
+        self.contains.push(vec![]);
+        self.contained_by.push(vec![]);
+        self.index.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    // Depth first search that fails on reaching a bag that's still on the
+    // path to it, naming every bag in the loop.
+    fn visit(
+        &self,
+        bag: usize,
+        state: &mut [Visit],
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), String> {
+        match state[bag] {
+            Visit::Done => return Ok(()),
+            Visit::OnPath => {
+                let start = path.iter().position(|b| *b == bag).unwrap();
+                let mut cycle: Vec<&str> = path[start..]
+                    .iter()
+                    .map(|b| self.names[*b].as_str())
+                    .collect();
+                cycle.push(&self.names[bag]);
+                return Err(format!("bags contain themselves: {}", cycle.join(" -> ")));
             }
+            Visit::New => (),
+        }
+
+        state[bag] = Visit::OnPath;
+        path.push(bag);
+        for (_, inner) in &self.contains[bag] {
+            self.visit(*inner, state, path, order)?;
         }
-        false
+        path.pop();
+        state[bag] = Visit::Done;
+        order.push(bag);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<usize, String> {
+        self.index
+            .get(name)
+            .copied()
+            .ok_or(format!("unknown bag: {}", name))
     }
 
-    fn count_bags_inside(&self) -> u32 {
-        let mut count = 0;
-        for (c, bag) in &self.contents {
-            let inside = bag.count_bags_inside();
-            count += c * inside;
-            count += c;
+    fn count_bags_inside(&self, name: &str) -> Result<u64, String> {
+        Ok(self.inside[self.lookup(name)?])
+    }
+
+    // Like `count_bags_inside`, but only opening bags `depth` levels deep.
+    // Once `depth` covers every level a bag holds the full total is used;
+    // otherwise results are memoised by bag and depth, since the same bag is
+    // often reached along many paths.
+    fn count_bags_within(&self, name: &str, depth: usize) -> Result<u64, String> {
+        fn count(
+            graph: &BagGraph,
+            bag: usize,
+            depth: usize,
+            memo: &mut HashMap<(usize, usize), u64>,
+        ) -> u64 {
+            if depth == 0 {
+                return 0;
+            }
+            if depth >= graph.levels[bag] {
+                return graph.inside[bag];
+            }
+            if let Some(n) = memo.get(&(bag, depth)) {
+                return *n;
+            }
+            let n = graph.contains[bag]
+                .iter()
+                .map(|(c, inner)| *c as u64 * (1 + count(graph, *inner, depth - 1, memo)))
+                .sum();
+            memo.insert((bag, depth), n);
+            n
         }
-        count
+        Ok(count(self, self.lookup(name)?, depth, &mut HashMap::new()))
     }
-}
 
-impl Bags {
-    fn new(rules: &mut dyn Iterator<Item = &BagRules>) -> Bags {
-        let mut bags = Bags {
-            bag_types: HashMap::new(),
-        };
+    // Every bag that can eventually hold `name`, going at most `depth`
+    // levels out if given.
+    fn containers(&self, name: &str, depth: Option<usize>) -> Result<BTreeSet<&str>, String> {
+        let seen = self.walk(self.lookup(name)?, depth, Direction::Out);
+        Ok((0..self.names.len())
+            .filter(|b| seen[*b])
+            .map(|b| self.names[b].as_str())
+            .collect())
+    }
+
+    // Breadth first search from `start`, either into the bags it holds or out
+    // to the bags holding it. `start` itself is only marked if it's reached
+    // again.
+    fn walk(&self, start: usize, depth: Option<usize>, direction: Direction) -> Vec<bool> {
+        let mut seen = vec![false; self.names.len()];
+        let mut queue = VecDeque::from(vec![(start, 0)]);
 
-        for ru in rules {
-            bags.bag_types.insert(ru.bag.clone(), ru.contents.clone());
+        while let Some((bag, d)) = queue.pop_front() {
+            if depth.is_some_and(|max| d >= max) {
+                continue;
+            }
+            let next: Vec<usize> = match direction {
+                Direction::In => self.contains[bag].iter().map(|(_, b)| *b).collect(),
+                Direction::Out => self.contained_by[bag].clone(),
+            };
+            for b in next {
+                if !seen[b] {
+                    seen[b] = true;
+                    queue.push_back((b, d + 1));
+                }
+            }
         }
+        seen
+    }
 
-        bags
+    fn is_leaf(&self, bag: usize) -> bool {
+        self.contains[bag].is_empty()
     }
 
-    fn resolve(&self, bag_name: &String) -> Bag {
-        let rules = self.bag_types.get(bag_name).unwrap();
-        let contents = rules
-            .iter()
-            .map(|rule| (rule.0, self.resolve(&rule.1)))
-            .collect();
+    // Renders the rules for Graphviz, with each edge labelled by its count.
+    // With `highlight`, the chosen bag and everything it holds (or that holds
+    // it) is drawn in red. With `collapse_leaves`, bags that hold nothing are
+    // folded into the labels of the bags holding them, apart from the chosen
+    // bag itself.
+    fn to_dot(
+        &self,
+        highlight: Option<(&str, Direction)>,
+        collapse_leaves: bool,
+    ) -> Result<String, String> {
+        let root = match highlight {
+            Some((name, direction)) => Some((self.lookup(name)?, direction)),
+            None => None,
+        };
+        let lit = match root {
+            Some((start, direction)) => {
+                let mut lit = self.walk(start, None, direction);
+                lit[start] = true;
+                lit
+            }
+            None => vec![false; self.names.len()],
+        };
+        let hidden = |b: usize| {
+            collapse_leaves
+                && root.map(|(r, _)| r) != Some(b)
+                && self.is_leaf(b)
+                && !self.contained_by[b].is_empty()
+        };
+        let red = |on: bool| if on { " color=red" } else { "" };
 
-        Bag {
-            name: bag_name.clone(),
-            contents: contents,
+        let mut out = String::from("digraph bags {\n");
+        for (bag, name) in self.names.iter().enumerate() {
+            if hidden(bag) {
+                continue;
+            }
+            let mut label = name.clone();
+            for (count, inner) in &self.contains[bag] {
+                if hidden(*inner) {
+                    label.push_str(&format!("\\n{} {}", count, self.names[*inner]));
+                }
+            }
+            out.push_str(&format!(
+                "  {:?} [label=\"{}\"{}];\n",
+                name,
+                label,
+                red(lit[bag])
+            ));
         }
+        for bag in 0..self.names.len() {
+            for (count, inner) in &self.contains[bag] {
+                if hidden(*inner) {
+                    continue;
+                }
+                out.push_str(&format!(
+                    "  {:?} -> {:?} [label=\"{}\"{}];\n",
+                    self.names[bag],
+                    self.names[*inner],
+                    count,
+                    red(lit[bag] && lit[*inner])
+                ));
+            }
+        }
+        out.push_str("}\n");
+        Ok(out)
     }
 }
 
-#[derive(Default, Debug, Clone)]
-struct Rule(u32, String);
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    // Towards the bags inside.
+    In,
+    // Towards the bags outside.
+    Out,
+}
 
-#[derive(Default, Debug)]
-struct BagRules {
-    bag: String,
-    contents: Vec<Rule>,
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    OnPath,
+    Done,
 }
 
-fn parse_bags(lines: Lines<StdinLock>) -> Result<Vec<BagRules>, io::Error> {
-    let line_re = Regex::new(r"(?P<bag_type>.*) bags contain (?P<rules>.*)").unwrap();
-    let rule_re = Regex::new(r"(?P<num>\d) (?P<type>.*) bag").unwrap();
-    let no_regex = Regex::new(r"no other bags").unwrap();
+fn parse_bags(lines: impl Iterator<Item = String>) -> Result<Vec<BagRules>, String> {
+    let line_re = Regex::new(r"^(?P<bag_type>.*) bags contain (?P<rules>.*)\.$").unwrap();
+    let rule_re = Regex::new(r"^(?P<num>\d+) (?P<type>.*) bags?$").unwrap();
 
-    let rules = lines
-        .map(|li| li.unwrap())
+    lines
+        .filter(|li| !li.is_empty())
         .map(|li| {
-            let caps = line_re.captures(li.as_str()).unwrap();
-            let rules = caps["rules"].split(",");
-
-            let r = rules
-                .map(|rule| {
-                    let caps = rule_re.captures(rule);
-                    match caps {
-                        Some(caps) => {
-                            Some(Rule(caps["num"].parse().unwrap(), caps["type"].to_string()))
-                        }
-                        None => None,
-                    }
-                })
-                .filter(|r| r.is_some())
-                .map(|r| r.unwrap())
-                .collect();
-
-            BagRules {
+            let caps = line_re
+                .captures(li.as_str())
+                .ok_or(format!("not a rule: {}", li))?;
+
+            let contents = if &caps["rules"] == "no other bags" {
+                vec![]
+            } else {
+                caps["rules"]
+                    .split(", ")
+                    .map(|rule| {
+                        let caps = rule_re
+                            .captures(rule)
+                            .ok_or(format!("not a bag count: {}", rule))?;
+                        let num = caps["num"]
+                            .parse()
+                            .map_err(|e| format!("{}: {}", rule, e))?;
+                        Ok(Rule(num, caps["type"].to_string()))
+                    })
+                    .collect::<Result<_, String>>()?
+            };
+
+            Ok(BagRules {
                 bag: caps["bag_type"].to_string(),
-                contents: r,
-            }
+                contents,
+            })
         })
-        .collect();
+        .collect()
+}
+
+const USAGE: &str = "usage: aoc7 [--dot | --dot-in | --dot-out] [--collapse] [bag [depth]]";
 
-    Ok(rules)
+fn usage(problem: &str) -> ! {
+    eprintln!("{}; {}", problem, USAGE);
+    process::exit(2);
 }
 
 fn main() {
-    let looking_for = "shiny gold".to_string();
+    // Optionally the bag to ask about, and a depth to stop at. `--dot` prints
+    // the rules for Graphviz instead, `--dot-in` and `--dot-out` highlight
+    // what the bag holds or what holds it, and `--collapse` hides leaf bags.
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if let Some(flag) = args.iter().find(|a| {
+        a.starts_with("--")
+            && !["--dot", "--dot-in", "--dot-out", "--collapse"].contains(&a.as_str())
+    }) {
+        usage(&format!("unknown flag {}", flag));
+    }
+    let flags: Vec<String> = args
+        .iter()
+        .filter(|a| a.starts_with("--"))
+        .cloned()
+        .collect();
+    args.retain(|a| !a.starts_with("--"));
+    let looking_for = args.first().map_or("shiny gold", |a| a.as_str());
+    let depth: Option<usize> = args.get(1).map(|a| {
+        a.parse()
+            .unwrap_or_else(|_| usage(&format!("bad depth {}", a)))
+    });
 
     let stdin = io::stdin();
-    let lines = stdin.lock().lines();
+    let lines = stdin.lock().lines().map(|li| li.unwrap());
     let bag_rules = parse_bags(lines).unwrap();
-    let bags = Bags::new(&mut bag_rules.iter());
-    let mut count = 0;
-    for rule in bag_rules {
-        if looking_for == rule.bag {
-            continue;
-        }
+    let bags = BagGraph::new(&bag_rules).unwrap();
+
+    let has = |flag: &str| flags.iter().any(|f| f == flag);
+    if has("--dot") || has("--dot-in") || has("--dot-out") {
+        let highlight = if has("--dot-in") {
+            Some((looking_for, Direction::In))
+        } else if has("--dot-out") {
+            Some((looking_for, Direction::Out))
+        } else {
+            None
+        };
+        print!("{}", bags.to_dot(highlight, has("--collapse")).unwrap());
+        return;
+    }
 
-        let resolved = bags.resolve(&rule.bag);
-        if resolved.find(&looking_for) {
-            count += 1;
+    println!("{}", bags.containers(looking_for, depth).unwrap().len());
+    match depth {
+        Some(depth) => println!("{}", bags.count_bags_within(looking_for, depth).unwrap()),
+        None => println!("{}", bags.count_bags_inside(looking_for).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(st: &str) -> Result<BagGraph, String> {
+        BagGraph::new(&parse_bags(st.lines().map(|l| l.to_string()))?)
+    }
+
+    #[test]
+    fn test_parse() {
+        let rules = parse_bags(
+            include_str!("../simple_input")
+                .lines()
+                .map(|l| l.to_string()),
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 9);
+        assert_eq!(rules[0].bag, "light red");
+        assert_eq!(
+            rules[0].contents,
+            vec![
+                Rule(1, "bright white".to_string()),
+                Rule(2, "muted yellow".to_string())
+            ]
+        );
+        assert!(rules[7].contents.is_empty());
+
+        assert!(parse_bags(
+            vec!["light red bags hold 1 bright white bag.".to_string()].into_iter()
+        )
+        .is_err());
+        assert!(parse_bags(
+            vec!["light red bags contain one bright white bag.".to_string()].into_iter()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_example() {
+        let bags = graph(include_str!("../simple_input")).unwrap();
+        assert_eq!(
+            bags.containers("shiny gold", None).unwrap(),
+            ["bright white", "muted yellow", "dark orange", "light red"]
+                .iter()
+                .copied()
+                .collect()
+        );
+        assert_eq!(bags.count_bags_inside("shiny gold").unwrap(), 32);
+        assert_eq!(bags.count_bags_inside("faded blue").unwrap(), 0);
+        assert!(bags.count_bags_inside("plaid").is_err());
+    }
+
+    #[test]
+    fn test_deep() {
+        let bags = graph(
+            "shiny gold bags contain 2 dark red bags.
+dark red bags contain 2 dark orange bags.
+dark orange bags contain 2 dark yellow bags.
+dark yellow bags contain 2 dark green bags.
+dark green bags contain 2 dark blue bags.
+dark blue bags contain 2 dark violet bags.
+dark violet bags contain no other bags.",
+        )
+        .unwrap();
+        assert_eq!(bags.count_bags_inside("shiny gold").unwrap(), 126);
+        assert_eq!(bags.count_bags_within("shiny gold", 0).unwrap(), 0);
+        assert_eq!(bags.count_bags_within("shiny gold", 2).unwrap(), 2 + 4);
+        assert_eq!(bags.count_bags_within("shiny gold", 100).unwrap(), 126);
+
+        assert_eq!(bags.containers("dark violet", Some(0)).unwrap().len(), 0);
+        assert_eq!(
+            bags.containers("dark violet", Some(2)).unwrap(),
+            ["dark blue", "dark green"].iter().copied().collect()
+        );
+        assert_eq!(bags.containers("dark violet", None).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_shared_within() {
+        // Each level holds two bags which both hold the next level, so there
+        // are 2^40 paths to the bottom.
+        let mut rules = String::new();
+        for i in 0..40 {
+            rules.push_str(&format!(
+                "level{} bags contain 1 left{} bag, 1 right{} bag.\n",
+                i, i, i
+            ));
+            for side in ["left", "right"].iter() {
+                rules.push_str(&format!(
+                    "{}{} bags contain 1 level{} bag.\n",
+                    side,
+                    i,
+                    i + 1
+                ));
+            }
         }
+        rules.push_str("level40 bags contain no other bags.\n");
+        let bags = graph(&rules).unwrap();
+
+        let inside = bags.count_bags_inside("level0").unwrap();
+        assert_eq!(bags.count_bags_within("level0", 80).unwrap(), inside);
+        // Only the 2^40 bags at the bottom are left unopened.
+        assert_eq!(
+            bags.count_bags_within("level0", 79).unwrap(),
+            inside - (1 << 40)
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let err = graph(
+            "light red bags contain 1 bright white bag.
+bright white bags contain 2 muted yellow bags.
+muted yellow bags contain 1 light red bag, 1 faded blue bag.
+faded blue bags contain no other bags.",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "bags contain themselves: light red -> bright white -> muted yellow -> light red"
+        );
+
+        assert!(graph("plaid bags contain 1 plaid bag.").is_err());
+        assert!(graph(
+            "plaid bags contain no other bags.
+plaid bags contain 1 faded blue bag."
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_dot() {
+        let bags = graph(
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain no other bags.
+shiny gold bags contain no other bags.",
+        )
+        .unwrap();
+
+        assert_eq!(
+            bags.to_dot(None, false).unwrap(),
+            r#"digraph bags {
+  "light red" [label="light red"];
+  "bright white" [label="bright white"];
+  "muted yellow" [label="muted yellow"];
+  "shiny gold" [label="shiny gold"];
+  "light red" -> "bright white" [label="1"];
+  "light red" -> "muted yellow" [label="2"];
+  "bright white" -> "shiny gold" [label="1"];
+}
+"#
+        );
+
+        assert_eq!(
+            bags.to_dot(Some(("shiny gold", Direction::Out)), true)
+                .unwrap(),
+            r#"digraph bags {
+  "light red" [label="light red\n2 muted yellow" color=red];
+  "bright white" [label="bright white" color=red];
+  "shiny gold" [label="shiny gold" color=red];
+  "light red" -> "bright white" [label="1" color=red];
+  "bright white" -> "shiny gold" [label="1" color=red];
+}
+"#
+        );
+
+        let dot = bags
+            .to_dot(Some(("bright white", Direction::In)), false)
+            .unwrap();
+        assert!(dot.contains(r#""bright white" -> "shiny gold" [label="1" color=red];"#));
+        assert!(dot.contains(r#""light red" -> "bright white" [label="1"];"#));
+        assert!(bags.to_dot(Some(("plaid", Direction::In)), false).is_err());
     }
-    println!("{}", count);
-    println!(
-        "{}",
-        bags.resolve(&"shiny gold".to_string()).count_bags_inside()
-    );
 }
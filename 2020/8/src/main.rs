@@ -1,86 +1,670 @@
-use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use std::io::{self, BufRead, Error, Lines, StdinLock};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Instruction {
     Nop(i32),
     Acc(i32),
     Jmp(i32),
 }
 
-fn parse_program(lines: Lines<StdinLock>) -> Result<Vec<Instruction>, io::Error> {
-    let line_re = Regex::new(r"(?P<opcode>\w+) (?P<operand>.*)").unwrap();
+impl Instruction {
+    fn parse(st: &str) -> Result<Instruction, String> {
+        let (opcode, operand) = st.split_once(' ').ok_or(format!("no operand: {:?}", st))?;
+        let operand: i32 = operand
+            .parse()
+            .map_err(|e| format!("{:?}: {}", operand, e))?;
+        match opcode {
+            "nop" => Ok(Instruction::Nop(operand)),
+            "acc" => Ok(Instruction::Acc(operand)),
+            "jmp" => Ok(Instruction::Jmp(operand)),
+            _ => Err(format!("unexpected opcode: {}", opcode)),
+        }
+    }
 
-    let program = lines
-        .map(|li| li.unwrap())
-        .map(|li| {
-            let caps = line_re.captures(&li).unwrap();
-            match &caps["opcode"] {
-                "nop" => Instruction::Nop(caps["operand"].parse().unwrap()),
-                "acc" => Instruction::Acc(caps["operand"].parse().unwrap()),
-                "jmp" => Instruction::Jmp(caps["operand"].parse().unwrap()),
-                _ => panic!("unexpected operand: {}", &caps["opcode"]),
-            }
+    // Where control goes after this instruction at `pc`.
+    fn next(&self, pc: usize) -> i64 {
+        match self {
+            Instruction::Jmp(n) => pc as i64 + *n as i64,
+            _ => pc as i64 + 1,
+        }
+    }
+
+    fn flipped(&self) -> Option<Instruction> {
+        match self {
+            Instruction::Nop(n) => Some(Instruction::Jmp(*n)),
+            Instruction::Jmp(n) => Some(Instruction::Nop(*n)),
+            Instruction::Acc(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Nop(n) => write!(f, "nop {:+}", n),
+            Instruction::Acc(n) => write!(f, "acc {:+}", n),
+            Instruction::Jmp(n) => write!(f, "jmp {:+}", n),
+        }
+    }
+}
+
+// Each instruction with the line it came from, counting from 1. Blank lines
+// are skipped but still counted.
+fn parse_program(lines: impl Iterator<Item = String>) -> Result<Vec<(usize, Instruction)>, String> {
+    lines
+        .enumerate()
+        .filter(|(_, li)| !li.is_empty())
+        .map(|(i, li)| {
+            Instruction::parse(&li)
+                .map(|inst| (i + 1, inst))
+                .map_err(|e| format!("line {}: {}", i + 1, e))
         })
-        .collect();
+        .collect()
+}
 
-    Ok(program)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct State {
+    pc: usize,
+    acc: i64,
+    steps: u64,
 }
 
-fn execute_program(program: &Vec<Instruction>) -> Result<i32, String> {
-    let mut accumulator = 0;
-    let mut pc: i32 = 0;
-    let mut total = 0;
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Breakpoint {
+    Pc(usize),
+    Acc(i64),
+}
 
-    let mut order: Vec<usize> = vec![0; program.len()];
+impl Breakpoint {
+    // Accumulator breakpoints only fire when the value changes to theirs, or
+    // they'd fire again on every step until it changed back.
+    fn hit(&self, before: &State, after: &State) -> bool {
+        match self {
+            Breakpoint::Pc(pc) => after.pc == *pc,
+            Breakpoint::Acc(acc) => after.acc == *acc && before.acc != *acc,
+        }
+    }
+}
+
+// Why the machine stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stop {
+    // Ran off the end of the program, which is how it's meant to finish.
+    Terminated,
+    // About to run an instruction for the second time.
+    Loop,
+    // Jumped somewhere other than an instruction or the end.
+    OutOfBounds(i64),
+    Breakpoint(Breakpoint),
+    // `run_until` was satisfied, or the steps asked for were taken.
+    Paused,
+}
+
+// Everything needed to put the machine back where it was.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    state: State,
+    visited: Vec<bool>,
+}
+
+#[derive(Debug)]
+struct Vm {
+    program: Vec<Instruction>,
+    state: State,
+    visited: Vec<bool>,
+    breakpoints: Vec<Breakpoint>,
+    // The state before each instruction run, and the instruction, while
+    // tracing is on.
+    trace: Option<Vec<(State, Instruction)>>,
+}
 
-    use crate::Instruction::*;
+impl Vm {
+    fn new(program: Vec<Instruction>) -> Vm {
+        let visited = vec![false; program.len()];
+        Vm {
+            program,
+            state: State::default(),
+            visited,
+            breakpoints: vec![],
+            trace: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = State::default();
+        self.visited = vec![false; self.program.len()];
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+    }
 
-    while pc < program.len() as i32 {
-        if order[pc as usize] != 0 {
-            return Err(format!("infinite loop! pc: {}, acc: {}", pc, accumulator));
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            state: self.state,
+            visited: self.visited.clone(),
         }
+    }
 
-        order[pc as usize] = total;
-        total += 1;
+    fn restore(&mut self, snapshot: &Snapshot) {
+        self.state = snapshot.state;
+        self.visited = snapshot.visited.clone();
+    }
 
-        let cur_inst = &program[pc as usize];
+    fn set_tracing(&mut self, on: bool) {
+        self.trace = if on { Some(vec![]) } else { None };
+    }
 
-        match cur_inst {
-            Nop(_) => pc += 1,
-            Acc(amt) => {
-                accumulator += amt;
-                pc += 1;
+    // Runs one instruction, unless the machine can't go any further.
+    fn step(&mut self) -> Option<Stop> {
+        let pc = self.state.pc;
+        if pc == self.program.len() {
+            return Some(Stop::Terminated);
+        }
+        if self.visited[pc] {
+            return Some(Stop::Loop);
+        }
+
+        let inst = self.program[pc];
+        let next = inst.next(pc);
+        if next < 0 || next > self.program.len() as i64 {
+            return Some(Stop::OutOfBounds(next));
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.push((self.state, inst));
+        }
+        self.visited[pc] = true;
+        if let Instruction::Acc(n) = inst {
+            self.state.acc += n as i64;
+        }
+        self.state.pc = next as usize;
+        self.state.steps += 1;
+        None
+    }
+
+    // Always takes at least one step, so continuing from a breakpoint
+    // doesn't stop straight away.
+    fn run_until(&mut self, mut done: impl FnMut(&State) -> bool) -> Stop {
+        loop {
+            let before = self.state;
+            if let Some(stop) = self.step() {
+                return stop;
+            }
+            if let Some(bp) = self
+                .breakpoints
+                .iter()
+                .find(|bp| bp.hit(&before, &self.state))
+            {
+                return Stop::Breakpoint(*bp);
+            }
+            if done(&self.state) {
+                return Stop::Paused;
+            }
+        }
+    }
+
+    fn run(&mut self) -> Stop {
+        self.run_until(|_| false)
+    }
+}
+
+// Runs `program` from the start to see how it stops, and with what in the
+// accumulator.
+fn execute_program(program: &[Instruction]) -> (Stop, i64) {
+    let mut vm = Vm::new(program.to_vec());
+    let stop = vm.run();
+    (stop, vm.state.acc)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Repair {
+    index: usize,
+    from: Instruction,
+    to: Instruction,
+    acc: i64,
+}
+
+// Finds the one `nop`/`jmp` swap that lets the program terminate. Working
+// back from the end finds every instruction that already leads there; the
+// fix is then the first instruction on the looping path whose swapped
+// successor is one of those. That path can't come back through the swapped
+// instruction, or it would have terminated unswapped, so one pass over the
+// program and one over the path is enough.
+fn repair(program: &[Instruction]) -> Result<Repair, String> {
+    let end = program.len();
+
+    let mut preds: Vec<Vec<usize>> = vec![vec![]; end + 1];
+    for (pc, inst) in program.iter().enumerate() {
+        let next = inst.next(pc);
+        if next >= 0 && next <= end as i64 {
+            preds[next as usize].push(pc);
+        }
+    }
+    let mut terminates = vec![false; end + 1];
+    terminates[end] = true;
+    let mut stack = vec![end];
+    while let Some(pc) = stack.pop() {
+        for prev in &preds[pc] {
+            if !terminates[*prev] {
+                terminates[*prev] = true;
+                stack.push(*prev);
+            }
+        }
+    }
+    if terminates[0] {
+        return Err("the program already terminates".to_string());
+    }
+
+    let mut vm = Vm::new(program.to_vec());
+    loop {
+        let pc = vm.state.pc;
+        if let Some(to) = program[pc].flipped() {
+            let next = to.next(pc);
+            if next >= 0 && next <= end as i64 && terminates[next as usize] {
+                let mut patched = program.to_vec();
+                patched[pc] = to;
+                let (stop, acc) = execute_program(&patched);
+                assert_eq!(stop, Stop::Terminated);
+                return Ok(Repair {
+                    index: pc,
+                    from: program[pc],
+                    to,
+                    acc,
+                });
             }
-            Jmp(num) => pc += *num,
-        };
+        }
+        if vm.step().is_some() {
+            return Err("no single swap fixes the program".to_string());
+        }
     }
-    Ok(accumulator)
+}
+
+// A line-based debugger, reading commands from `input` so it can be scripted.
+fn repl(vm: &mut Vm, input: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+    let mut snapshots: HashMap<String, Snapshot> = HashMap::new();
+
+    for line in input.lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let state = vm.state;
+
+        match words[..] {
+            [] => continue,
+            ["step"] | ["s"] => match vm.run_until(|_| true) {
+                Stop::Paused => writeln!(out, "{}", Status(vm))?,
+                stop => writeln!(out, "{:?} {}", stop, Status(vm))?,
+            },
+            ["step", n] | ["s", n] => match n.parse::<u64>() {
+                Ok(n) => {
+                    let target = state.steps + n;
+                    let stop = vm.run_until(|s| s.steps >= target);
+                    writeln!(out, "{:?} {}", stop, Status(vm))?
+                }
+                Err(e) => writeln!(out, "error: {}", e)?,
+            },
+            ["continue"] | ["c"] => {
+                let stop = vm.run();
+                writeln!(out, "{:?} {}", stop, Status(vm))?
+            }
+            ["break", "pc", n] => match n.parse() {
+                Ok(pc) => vm.breakpoints.push(Breakpoint::Pc(pc)),
+                Err(e) => writeln!(out, "error: {}", e)?,
+            },
+            ["break", "acc", n] => match n.parse() {
+                Ok(acc) => vm.breakpoints.push(Breakpoint::Acc(acc)),
+                Err(e) => writeln!(out, "error: {}", e)?,
+            },
+            ["breakpoints"] => {
+                for (i, bp) in vm.breakpoints.iter().enumerate() {
+                    writeln!(out, "{} {:?}", i, bp)?;
+                }
+            }
+            ["delete", i] => match i.parse::<usize>() {
+                Ok(i) if i < vm.breakpoints.len() => {
+                    vm.breakpoints.remove(i);
+                }
+                _ => writeln!(out, "error: no breakpoint {}", i)?,
+            },
+            ["print"] | ["p"] => writeln!(out, "{}", Status(vm))?,
+            ["save", name] => {
+                snapshots.insert(name.to_string(), vm.snapshot());
+            }
+            ["load", name] => match snapshots.get(name) {
+                Some(snapshot) => {
+                    vm.restore(snapshot);
+                    writeln!(out, "{}", Status(vm))?
+                }
+                None => writeln!(out, "error: no snapshot {}", name)?,
+            },
+            ["trace", "on"] => vm.set_tracing(true),
+            ["trace", "off"] => vm.set_tracing(false),
+            ["trace"] => match &vm.trace {
+                Some(trace) => {
+                    for (state, inst) in trace {
+                        writeln!(
+                            out,
+                            "{:>5} {:>4} {:>6} {}",
+                            state.steps, state.pc, state.acc, inst
+                        )?;
+                    }
+                }
+                None => writeln!(out, "error: tracing is off")?,
+            },
+            ["reset"] => {
+                vm.reset();
+                writeln!(out, "{}", Status(vm))?
+            }
+            ["quit"] | ["q"] => break,
+            _ => writeln!(out, "error: unknown command: {}", line)?,
+        }
+    }
+    Ok(())
+}
+
+// The machine's state and the instruction it's about to run.
+struct Status<'a>(&'a Vm);
+
+impl fmt::Display for Status<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let vm = self.0;
+        write!(
+            f,
+            "pc={} acc={} steps={}",
+            vm.state.pc, vm.state.acc, vm.state.steps
+        )?;
+        match vm.program.get(vm.state.pc) {
+            Some(inst) => write!(f, " next: {}", inst),
+            None => write!(f, " next: end"),
+        }
+    }
+}
+
+const USAGE: &str = "usage: aoc8 [--debug <program> [script]]";
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{}; {}", problem, USAGE);
+    process::exit(2);
 }
 
 fn main() {
-    let stdin = io::stdin();
-    let lines = stdin.lock().lines();
+    // `--debug <program> [script]` starts the debugger, reading commands
+    // from the script or stdin.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(|a| a.as_str()) == Some("--debug") {
+        let path = args
+            .get(1)
+            .unwrap_or_else(|| usage("--debug needs a program"));
+        let source = fs::read_to_string(path).unwrap();
+        let program = parse_program(source.lines().map(|l| l.to_string())).unwrap();
+        let mut vm = Vm::new(program.into_iter().map(|(_, inst)| inst).collect());
+
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        match args.get(2) {
+            Some(script) => {
+                let file = fs::File::open(script).unwrap();
+                repl(&mut vm, io::BufReader::new(file), &mut out).unwrap();
+            }
+            None => repl(&mut vm, io::stdin().lock(), &mut out).unwrap(),
+        }
+        return;
+    }
 
-    let mut prog = parse_program(lines).unwrap();
+    let stdin = io::stdin();
+    let lines = stdin.lock().lines().map(|li| li.unwrap());
+    let (line_numbers, prog): (Vec<usize>, Vec<Instruction>) =
+        parse_program(lines).unwrap().into_iter().unzip();
 
     let result = execute_program(&prog);
     println!("{:?}", result);
 
-    for idx in 0..prog.len() {
-        let inst = &prog[idx];
-        let tmp = inst.clone();
-        let new_inst = match inst {
-            Instruction::Nop(k) => Instruction::Jmp(*k),
-            Instruction::Jmp(k) => Instruction::Nop(*k),
-            Instruction::Acc(k) => Instruction::Acc(*k),
-        };
-        prog[idx] = new_inst;
-        let result = execute_program(&prog);
-        prog[idx] = tmp;
-        if result.is_ok() {
-            println!("{:?}", result);
+    match repair(&prog) {
+        Ok(fix) => println!(
+            "line {}: {} -> {}, acc {}",
+            line_numbers[fix.index], fix.from, fix.to, fix.acc
+        ),
+        Err(e) => println!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Instruction> {
+        parse_program(
+            include_str!("../simple_input")
+                .lines()
+                .map(|l| l.to_string()),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|(_, inst)| inst)
+        .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let program = example();
+        assert_eq!(program.len(), 9);
+        assert_eq!(program[4], Instruction::Jmp(-3));
+        assert_eq!(program[4].to_string(), "jmp -3");
+        assert!(Instruction::parse("mul +3").is_err());
+        assert!(Instruction::parse("acc").is_err());
+        assert!(Instruction::parse("acc x").is_err());
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(execute_program(&example()), (Stop::Loop, 5));
+
+        let mut fixed = example();
+        fixed[7] = Instruction::Nop(-4);
+        assert_eq!(execute_program(&fixed), (Stop::Terminated, 8));
+
+        // Revisiting the very first instruction counts as a loop too.
+        assert_eq!(
+            execute_program(&[Instruction::Acc(1), Instruction::Jmp(-1)]),
+            (Stop::Loop, 1)
+        );
+        assert_eq!(
+            execute_program(&[Instruction::Jmp(-1)]),
+            (Stop::OutOfBounds(-1), 0)
+        );
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut vm = Vm::new(example());
+        vm.breakpoints.push(Breakpoint::Pc(3));
+        vm.breakpoints.push(Breakpoint::Acc(2));
+
+        // nop, acc +1, jmp +4, acc +1 leaves acc at 2.
+        assert_eq!(vm.run(), Stop::Breakpoint(Breakpoint::Acc(2)));
+        assert_eq!(vm.state.pc, 7);
+        assert_eq!(vm.run(), Stop::Breakpoint(Breakpoint::Pc(3)));
+        // acc stays 2 on the way to pc 3, which doesn't fire again.
+        assert_eq!(vm.run(), Stop::Loop);
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let mut vm = Vm::new(example());
+        assert_eq!(vm.run_until(|s| s.steps == 3), Stop::Paused);
+        let snapshot = vm.snapshot();
+        assert_eq!(
+            snapshot.state,
+            State {
+                pc: 6,
+                acc: 1,
+                steps: 3
+            }
+        );
+
+        assert_eq!(vm.run(), Stop::Loop);
+        assert_eq!(vm.state.acc, 5);
+
+        vm.restore(&snapshot);
+        assert_eq!(vm.snapshot(), snapshot);
+        assert_eq!(vm.run(), Stop::Loop);
+        assert_eq!(vm.state.acc, 5);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut vm = Vm::new(example());
+        vm.set_tracing(true);
+        vm.run();
+        let pcs: Vec<usize> = vm.trace.unwrap().iter().map(|(s, _)| s.pc).collect();
+        assert_eq!(pcs, vec![0, 1, 2, 6, 7, 3, 4]);
+    }
+
+    #[test]
+    fn test_repl() {
+        let script = "\
+break acc 2
+s
+step 2
+trace on
+c
+save here
+c
+trace
+load here
+delete 0
+breakpoints
+bogus
+c
+reset
+q
+p
+";
+        let mut vm = Vm::new(example());
+        let mut out = vec![];
+        repl(&mut vm, script.as_bytes(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+pc=1 acc=0 steps=1 next: acc +1
+Paused pc=6 acc=1 steps=3 next: acc +1
+Breakpoint(Acc(2)) pc=7 acc=2 steps=4 next: jmp -4
+Loop pc=1 acc=5 steps=7 next: acc +1
+    3    6      1 acc +1
+    4    7      2 jmp -4
+    5    3      2 acc +3
+    6    4      5 jmp -3
+pc=7 acc=2 steps=4 next: jmp -4
+error: unknown command: bogus
+Loop pc=1 acc=5 steps=7 next: acc +1
+pc=0 acc=0 steps=0 next: nop +0
+"
+        );
+    }
+
+    #[test]
+    fn test_repair() {
+        assert_eq!(
+            repair(&example()),
+            Ok(Repair {
+                index: 7,
+                from: Instruction::Jmp(-4),
+                to: Instruction::Nop(-4),
+                acc: 8,
+            })
+        );
+
+        let mut fixed = example();
+        fixed[7] = Instruction::Nop(-4);
+        assert!(repair(&fixed).is_err());
+        assert!(repair(&[
+            Instruction::Acc(1),
+            Instruction::Jmp(-1),
+            Instruction::Acc(1),
+            Instruction::Jmp(-1),
+        ])
+        .is_err());
+    }
+
+    // Tries every swap, rerunning the whole program each time.
+    fn repair_by_brute_force(program: &[Instruction]) -> Vec<(usize, i64)> {
+        let mut fixes = vec![];
+        for (idx, inst) in program.iter().enumerate() {
+            if let Some(to) = inst.flipped() {
+                let mut patched = program.to_vec();
+                patched[idx] = to;
+                if let (Stop::Terminated, acc) = execute_program(&patched) {
+                    fixes.push((idx, acc));
+                }
+            }
+        }
+        fixes
+    }
+
+    // Every program of `len` instructions whose jumps land within one
+    // instruction of either end. Each `acc` adds a different power of two, so
+    // the accumulator shows exactly which ones ran.
+    fn all_programs(len: usize) -> Vec<Vec<Instruction>> {
+        let offsets = -(len as i32)..=len as i32;
+        let mut programs: Vec<Vec<Instruction>> = vec![vec![]];
+        for pc in 0..len {
+            let mut choices = vec![Instruction::Acc(1 << pc)];
+            for n in offsets.clone() {
+                choices.push(Instruction::Nop(n));
+                choices.push(Instruction::Jmp(n));
+            }
+            programs = programs
+                .iter()
+                .flat_map(|p| {
+                    choices.iter().map(move |inst| {
+                        let mut p = p.clone();
+                        p.push(*inst);
+                        p
+                    })
+                })
+                .collect();
         }
+        programs
+    }
+
+    #[test]
+    fn test_repair_matches_brute_force() {
+        // Swapping an instruction the program never reaches can't help, so
+        // the brute force should find a fix exactly when `repair` does.
+        let mut fixed = 0;
+        for len in 1..=4 {
+            for program in all_programs(len) {
+                if execute_program(&program).0 == Stop::Terminated {
+                    continue;
+                }
+
+                let brute = repair_by_brute_force(&program);
+                match repair(&program) {
+                    Ok(fix) => {
+                        assert!(brute.contains(&(fix.index, fix.acc)), "{:?}", program);
+                        fixed += 1;
+                    }
+                    Err(_) => assert!(brute.is_empty(), "{:?}", program),
+                }
+            }
+        }
+        assert!(fixed > 1000);
+    }
+
+    #[test]
+    fn test_parse_line_numbers() {
+        let lines = ["nop +0", "", "jmp -1", "", "mul +2"];
+        let err = parse_program(lines.iter().map(|l| l.to_string())).unwrap_err();
+        assert!(err.starts_with("line 5:"), "{}", err);
+
+        let program = parse_program(lines[..4].iter().map(|l| l.to_string())).unwrap();
+        assert_eq!(
+            program,
+            vec![(1, Instruction::Nop(0)), (3, Instruction::Jmp(-1))]
+        );
     }
 }
@@ -0,0 +1,307 @@
+use std::{
+    fmt,
+    io::{self, BufRead},
+    ops::Add,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Number {
+    Regular(u32),
+    Pair(Box<Number>, Box<Number>),
+}
+
+impl Number {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let (n, consumed) = Self::parse_prefix(st.trim())?;
+        if consumed != st.trim().len() {
+            return Err(format!("trailing input after {}", n).into());
+        }
+        Ok(n)
+    }
+
+    fn parse_prefix(st: &str) -> Result<(Self, usize), Box<dyn std::error::Error>> {
+        match st.chars().next() {
+            Some('[') => {
+                let (left, left_len) = Self::parse_prefix(&st[1..])?;
+                let mut offset = 1 + left_len;
+                if !st[offset..].starts_with(',') {
+                    return Err(format!("expected ',' at {}", offset).into());
+                }
+                offset += 1;
+
+                let (right, right_len) = Self::parse_prefix(&st[offset..])?;
+                offset += right_len;
+                if !st[offset..].starts_with(']') {
+                    return Err(format!("expected ']' at {}", offset).into());
+                }
+                offset += 1;
+
+                Ok((Number::Pair(Box::new(left), Box::new(right)), offset))
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let len = st.chars().take_while(|c| c.is_ascii_digit()).count();
+                Ok((Number::Regular(st[..len].parse()?), len))
+            }
+            Some(c) => Err(format!("unexpected character: {}", c).into()),
+            None => Err("unexpected end of input".into()),
+        }
+    }
+
+    fn magnitude(&self) -> u32 {
+        use Number::*;
+        match self {
+            Regular(n) => *n,
+            Pair(left, right) => 3 * left.magnitude() + 2 * right.magnitude(),
+        }
+    }
+
+    fn reduce(mut self) -> Self {
+        while self.explode() || self.split() {}
+        self
+    }
+
+    fn explode(&mut self) -> bool {
+        self.explode_at(0).is_some()
+    }
+
+    // Returns the (left, right) values still waiting to be added to the
+    // nearest regular numbers outside of this subtree.
+    fn explode_at(&mut self, depth: usize) -> Option<(Option<u32>, Option<u32>)> {
+        use Number::*;
+        match self {
+            Regular(_) => None,
+            Pair(left, right) => {
+                if depth >= 4 {
+                    if let (Regular(l), Regular(r)) = (left.as_ref(), right.as_ref()) {
+                        let carry = (Some(*l), Some(*r));
+                        *self = Regular(0);
+                        return Some(carry);
+                    }
+                }
+
+                if let Some((l, r)) = left.explode_at(depth + 1) {
+                    if let Some(r) = r {
+                        right.add_leftmost(r);
+                    }
+                    return Some((l, None));
+                }
+
+                if let Some((l, r)) = right.explode_at(depth + 1) {
+                    if let Some(l) = l {
+                        left.add_rightmost(l);
+                    }
+                    return Some((None, r));
+                }
+
+                None
+            }
+        }
+    }
+
+    fn add_leftmost(&mut self, v: u32) {
+        use Number::*;
+        match self {
+            Regular(n) => *n += v,
+            Pair(left, _) => left.add_leftmost(v),
+        }
+    }
+
+    fn add_rightmost(&mut self, v: u32) {
+        use Number::*;
+        match self {
+            Regular(n) => *n += v,
+            Pair(_, right) => right.add_rightmost(v),
+        }
+    }
+
+    fn split(&mut self) -> bool {
+        use Number::*;
+        match self {
+            Regular(n) if *n >= 10 => {
+                *self = Pair(Box::new(Regular(*n / 2)), Box::new(Regular(n.div_ceil(2))));
+                true
+            }
+            Regular(_) => false,
+            Pair(left, right) => left.split() || right.split(),
+        }
+    }
+}
+
+impl Add for Number {
+    type Output = Number;
+
+    fn add(self, other: Number) -> Number {
+        Number::Pair(Box::new(self), Box::new(other)).reduce()
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Number::*;
+        match self {
+            Regular(n) => write!(f, "{}", n),
+            Pair(left, right) => write!(f, "[{},{}]", left, right),
+        }
+    }
+}
+
+fn sum(numbers: &[Number]) -> Option<Number> {
+    numbers.iter().cloned().reduce(|acc, n| acc + n)
+}
+
+fn largest_pair_magnitude(numbers: &[Number]) -> Option<u32> {
+    let mut max = None;
+    for (i, a) in numbers.iter().enumerate() {
+        for (j, b) in numbers.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let m = (a.clone() + b.clone()).magnitude();
+            max = max.max(Some(m));
+        }
+    }
+    max
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let numbers: Vec<Number> = stdin
+        .lock()
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| !line.is_empty())
+        .map(|line| Number::parse(&line).unwrap())
+        .collect();
+
+    // Part 1
+    let total = sum(&numbers).unwrap();
+    println!("{}", total);
+    println!("{}", total.magnitude());
+
+    // Part 2
+    println!("{:?}", largest_pair_magnitude(&numbers).unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(st: &str) -> Vec<Number> {
+        st.lines()
+            .map(|line| Number::parse(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for st in [
+            "[1,2]",
+            "[[1,2],3]",
+            "[9,[8,7]]",
+            "[[1,9],[8,5]]",
+            "[[[[1,2],[3,4]],[[5,6],[7,8]]],9]",
+            "[[[9,[3,8]],[[0,9],6]],[[[3,7],[4,9]],3]]",
+            "[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]",
+        ] {
+            assert_eq!(Number::parse(st).unwrap().to_string(), st);
+        }
+
+        assert!(Number::parse("[1,2").is_err());
+        assert!(Number::parse("[1;2]").is_err());
+        assert!(Number::parse("[1,2]]").is_err());
+    }
+
+    #[test]
+    fn test_explode() {
+        for (before, after) in [
+            ("[[[[[9,8],1],2],3],4]", "[[[[0,9],2],3],4]"),
+            ("[7,[6,[5,[4,[3,2]]]]]", "[7,[6,[5,[7,0]]]]"),
+            ("[[6,[5,[4,[3,2]]]],1]", "[[6,[5,[7,0]]],3]"),
+            (
+                "[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]",
+                "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]",
+            ),
+            (
+                "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]",
+                "[[3,[2,[8,0]]],[9,[5,[7,0]]]]",
+            ),
+        ] {
+            let mut n = Number::parse(before).unwrap();
+            assert!(n.explode());
+            assert_eq!(n.to_string(), after);
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        let mut n = Number::parse("[[[[0,7],4],[15,[0,13]]],[1,1]]").unwrap();
+        assert!(n.split());
+        assert_eq!(n.to_string(), "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]");
+        assert!(n.split());
+        assert_eq!(n.to_string(), "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]");
+        assert!(!n.split());
+    }
+
+    #[test]
+    fn test_add() {
+        let a = Number::parse("[[[[4,3],4],4],[7,[[8,4],9]]]").unwrap();
+        let b = Number::parse("[1,1]").unwrap();
+        assert_eq!((a + b).to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+    }
+
+    #[test]
+    fn test_sum() {
+        let numbers = parse_all("[1,1]\n[2,2]\n[3,3]\n[4,4]\n[5,5]\n[6,6]");
+        assert_eq!(
+            sum(&numbers).unwrap().to_string(),
+            "[[[[5,0],[7,4]],[5,5]],[6,6]]"
+        );
+
+        let numbers = parse_all(
+            "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]
+[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]
+[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]
+[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]
+[7,[5,[[3,8],[1,4]]]]
+[[2,[2,2]],[8,[8,1]]]
+[2,9]
+[1,[[[9,3],9],[[9,0],[0,7]]]]
+[[[5,[7,4]],7],1]
+[[[[4,2],2],6],[8,7]]",
+        );
+        assert_eq!(
+            sum(&numbers).unwrap().to_string(),
+            "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]"
+        );
+    }
+
+    #[test]
+    fn test_magnitude() {
+        for (st, magnitude) in [
+            ("[[1,2],[[3,4],5]]", 143),
+            ("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", 1384),
+            ("[[[[1,1],[2,2]],[3,3]],[4,4]]", 445),
+            ("[[[[3,0],[5,3]],[4,4]],[5,5]]", 791),
+            ("[[[[5,0],[7,4]],[5,5]],[6,6]]", 1137),
+            (
+                "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]",
+                3488,
+            ),
+        ] {
+            assert_eq!(Number::parse(st).unwrap().magnitude(), magnitude);
+        }
+    }
+
+    #[test]
+    fn test_homework() {
+        let numbers = parse_all(include_str!("../input/sample"));
+
+        let total = sum(&numbers).unwrap();
+        assert_eq!(
+            total.to_string(),
+            "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]"
+        );
+        assert_eq!(total.magnitude(), 4140);
+        assert_eq!(largest_pair_magnitude(&numbers), Some(3993));
+    }
+}
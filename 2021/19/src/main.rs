@@ -0,0 +1,422 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::{self, Read},
+    ops::{Add, Sub},
+};
+
+// Two scanners overlap when they share at least this many beacons, which
+// means they also share at least 12 choose 2 pairwise distances.
+const MIN_OVERLAP: usize = 12;
+const MIN_SHARED_DISTANCES: usize = MIN_OVERLAP * (MIN_OVERLAP - 1) / 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Point(i32, i32, i32);
+
+impl Point {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut sp = st.trim().split(',');
+        Ok(Point(
+            sp.next().ok_or("no x")?.parse()?,
+            sp.next().ok_or("no y")?.parse()?,
+            sp.next().ok_or("no z")?.parse()?,
+        ))
+    }
+
+    fn distance_squared(&self, other: &Point) -> i64 {
+        let d = *self - *other;
+        (d.0 as i64).pow(2) + (d.1 as i64).pow(2) + (d.2 as i64).pow(2)
+    }
+
+    fn manhattan(&self, other: &Point) -> i32 {
+        let d = *self - *other;
+        d.0.abs() + d.1.abs() + d.2.abs()
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
+}
+
+// One of the 24 proper rotations of the cube, stored as a signed permutation
+// matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Orientation([[i32; 3]; 3]);
+
+impl Orientation {
+    fn identity() -> Self {
+        Orientation([[1, 0, 0], [0, 1, 0], [0, 0, 1]])
+    }
+
+    fn all() -> Vec<Orientation> {
+        let permutations = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+
+        let mut v = vec![];
+        for perm in permutations {
+            for signs in 0..8 {
+                let mut m = [[0; 3]; 3];
+                for (row, col) in perm.iter().enumerate() {
+                    m[row][*col] = if signs & (1 << row) == 0 { 1 } else { -1 };
+                }
+
+                // Reflections have determinant -1; a scanner can only be
+                // rotated.
+                let o = Orientation(m);
+                if o.determinant() == 1 {
+                    v.push(o);
+                }
+            }
+        }
+        v
+    }
+
+    fn determinant(&self) -> i32 {
+        let m = &self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    fn apply(&self, p: &Point) -> Point {
+        let m = &self.0;
+        let v = [p.0, p.1, p.2];
+        let row = |r: usize| m[r][0] * v[0] + m[r][1] * v[1] + m[r][2] * v[2];
+        Point(row(0), row(1), row(2))
+    }
+
+    fn compose(&self, other: &Orientation) -> Orientation {
+        let mut m = [[0; 3]; 3];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = (0..3).map(|k| self.0[r][k] * other.0[k][c]).sum();
+            }
+        }
+        Orientation(m)
+    }
+
+    #[cfg(test)]
+    fn inverse(&self) -> Orientation {
+        let mut m = [[0; 3]; 3];
+        for (r, row) in m.iter_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = self.0[c][r];
+            }
+        }
+        Orientation(m)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Scanner {
+    id: usize,
+    beacons: Vec<Point>,
+}
+
+impl Scanner {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut lines = st.lines().filter(|line| !line.is_empty());
+        let header = lines.next().ok_or("no scanner header")?;
+        let id = header
+            .trim_start_matches("--- scanner ")
+            .trim_end_matches(" ---")
+            .parse()?;
+        let beacons = lines.map(Point::parse).collect::<Result<_, _>>()?;
+        Ok(Scanner { id, beacons })
+    }
+
+    fn fingerprint(&self) -> HashMap<i64, usize> {
+        let mut fp = HashMap::new();
+        for (i, a) in self.beacons.iter().enumerate() {
+            for b in &self.beacons[i + 1..] {
+                *fp.entry(a.distance_squared(b)).or_insert(0) += 1;
+            }
+        }
+        fp
+    }
+
+    // Finds the orientation and offset which map this scanner's beacons onto
+    // `reference`'s frame, if the two share enough beacons.
+    fn align(&self, reference: &Scanner) -> Option<(Orientation, Point)> {
+        for orientation in Orientation::all() {
+            let rotated: Vec<Point> = self.beacons.iter().map(|b| orientation.apply(b)).collect();
+
+            let mut votes: HashMap<Point, usize> = HashMap::new();
+            for r in &reference.beacons {
+                for b in &rotated {
+                    *votes.entry(*r - *b).or_insert(0) += 1;
+                }
+            }
+
+            if let Some((offset, _)) = votes.into_iter().find(|(_, n)| *n >= MIN_OVERLAP) {
+                return Some((orientation, offset));
+            }
+        }
+        None
+    }
+
+    fn transform(&self, orientation: &Orientation, offset: &Point) -> Scanner {
+        Scanner {
+            id: self.id,
+            beacons: self
+                .beacons
+                .iter()
+                .map(|b| orientation.apply(b) + *offset)
+                .collect(),
+        }
+    }
+}
+
+fn shared_distances(a: &HashMap<i64, usize>, b: &HashMap<i64, usize>) -> usize {
+    a.iter()
+        .map(|(d, n)| b.get(d).map_or(0, |m| *n.min(m)))
+        .sum()
+}
+
+#[derive(Debug)]
+struct BeaconMap {
+    beacons: HashSet<Point>,
+    scanners: Vec<Point>,
+}
+
+impl BeaconMap {
+    // Places every scanner in scanner 0's frame by walking outwards from it,
+    // only attempting alignment between pairs whose distance fingerprints
+    // overlap enough to plausibly share 12 beacons.
+    fn build(scanners: &[Scanner]) -> Result<Self, Box<dyn std::error::Error>> {
+        let fingerprints: Vec<HashMap<i64, usize>> =
+            scanners.iter().map(|s| s.fingerprint()).collect();
+
+        // Each placed scanner's orientation and position in scanner 0's frame.
+        let mut placed: Vec<Option<(Orientation, Point)>> = vec![None; scanners.len()];
+        placed[0] = Some((Orientation::identity(), Point(0, 0, 0)));
+
+        let mut q = VecDeque::from([0]);
+        while let Some(cur) = q.pop_front() {
+            let (cur_orientation, cur_position) = placed[cur].unwrap();
+            for (i, candidate) in scanners.iter().enumerate() {
+                if placed[i].is_some()
+                    || shared_distances(&fingerprints[cur], &fingerprints[i]) < MIN_SHARED_DISTANCES
+                {
+                    continue;
+                }
+
+                if let Some((orientation, offset)) = candidate.align(&scanners[cur]) {
+                    placed[i] = Some((
+                        cur_orientation.compose(&orientation),
+                        cur_orientation.apply(&offset) + cur_position,
+                    ));
+                    q.push_back(i);
+                }
+            }
+        }
+
+        let mut beacons = HashSet::new();
+        let mut positions = vec![];
+        for (scanner, p) in scanners.iter().zip(placed) {
+            let (orientation, position) =
+                p.ok_or_else(|| format!("could not place scanner {}", scanner.id))?;
+            beacons.extend(scanner.transform(&orientation, &position).beacons);
+            positions.push(position);
+        }
+
+        Ok(BeaconMap {
+            beacons,
+            scanners: positions,
+        })
+    }
+
+    fn max_scanner_distance(&self) -> i32 {
+        let mut max = 0;
+        for a in &self.scanners {
+            for b in &self.scanners {
+                max = max.max(a.manhattan(b));
+            }
+        }
+        max
+    }
+}
+
+fn parse_scanners(st: &str) -> Result<Vec<Scanner>, Box<dyn std::error::Error>> {
+    st.split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(Scanner::parse)
+        .collect()
+}
+
+fn main() {
+    let stdin = io::stdin();
+
+    let mut st = String::new();
+    stdin.lock().read_to_string(&mut st).unwrap();
+
+    let scanners = parse_scanners(&st).unwrap();
+    let map = BeaconMap::build(&scanners).unwrap();
+
+    // Part 1
+    println!("{}", map.beacons.len());
+
+    // Part 2
+    println!("{}", map.max_scanner_distance());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `count` beacons spread up to `spread` either side of `centre` on each
+    // axis, the same every run for a given `seed`.
+    fn scatter(seed: u64, count: usize, centre: Point, spread: Point) -> Vec<Point> {
+        let mut state = seed;
+        let mut next = |bound: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % (2 * bound as u64 + 1)) as i32 - bound
+        };
+        (0..count)
+            .map(|_| {
+                Point(
+                    centre.0 + next(spread.0),
+                    centre.1 + next(spread.1),
+                    centre.2 + next(spread.2),
+                )
+            })
+            .collect()
+    }
+
+    // Describes what a scanner at `position`, rotated by `orientation`, would
+    // report for every beacon within 1000 units on each axis.
+    fn observe(id: usize, world: &[Point], position: Point, orientation: &Orientation) -> Scanner {
+        let inverse = orientation.inverse();
+        let beacons = world
+            .iter()
+            .map(|b| *b - position)
+            .filter(|d| d.0.abs() <= 1000 && d.1.abs() <= 1000 && d.2.abs() <= 1000)
+            .map(|d| inverse.apply(&d))
+            .collect();
+        Scanner { id, beacons }
+    }
+
+    #[test]
+    fn test_orientation_group() {
+        let all = Orientation::all();
+        assert_eq!(all.len(), 24);
+
+        let unique: HashSet<Orientation> = all.iter().cloned().collect();
+        assert_eq!(unique.len(), 24);
+
+        for a in &all {
+            assert_eq!(a.compose(&a.inverse()), Orientation::identity());
+            for b in &all {
+                assert!(unique.contains(&a.compose(b)));
+            }
+        }
+
+        // Every orientation sends a distinct-coordinate point somewhere new.
+        let p = Point(1, 2, 3);
+        let images: HashSet<Point> = all.iter().map(|o| o.apply(&p)).collect();
+        assert_eq!(images.len(), 24);
+    }
+
+    #[test]
+    fn test_parse() {
+        let scanners = parse_scanners(
+            "--- scanner 0 ---
+0,2,0
+4,1,0
+3,3,0
+
+--- scanner 1 ---
+-1,-1,0
+-5,0,0
+-2,1,0
+",
+        )
+        .unwrap();
+
+        assert_eq!(scanners.len(), 2);
+        assert_eq!(scanners[1].id, 1);
+        assert_eq!(scanners[1].beacons[1], Point(-5, 0, 0));
+    }
+
+    #[test]
+    fn test_align() {
+        let world = scatter(19, 40, Point(0, 0, 0), Point(500, 500, 500));
+
+        let all = Orientation::all();
+        let reference = observe(0, &world, Point(0, 0, 0), &Orientation::identity());
+        let position = Point(68, -246, -43);
+        let other = observe(1, &world, position, &all[17]);
+
+        let (orientation, offset) = other.align(&reference).unwrap();
+        assert_eq!(orientation, all[17]);
+        assert_eq!(offset, position);
+    }
+
+    #[test]
+    fn test_build_map() {
+        let world = scatter(2021, 400, Point(1500, 0, 0), Point(2000, 900, 900));
+
+        let all = Orientation::all();
+        let positions = [
+            Point(0, 0, 0),
+            Point(2000, 40, -60),
+            Point(1000, -30, 20),
+            Point(3000, 10, 50),
+        ];
+        let scanners: Vec<Scanner> = positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let orientation = if i == 0 {
+                    Orientation::identity()
+                } else {
+                    all[i * 5]
+                };
+                observe(i, &world, *p, &orientation)
+            })
+            .collect();
+
+        let map = BeaconMap::build(&scanners).unwrap();
+        assert_eq!(map.scanners, positions.to_vec());
+
+        let expected: HashSet<Point> = world
+            .iter()
+            .filter(|b| {
+                positions.iter().any(|p| {
+                    let d = **b - *p;
+                    d.0.abs() <= 1000 && d.1.abs() <= 1000 && d.2.abs() <= 1000
+                })
+            })
+            .cloned()
+            .collect();
+        assert_eq!(map.beacons, expected);
+        assert_eq!(map.max_scanner_distance(), 3000 + 10 + 50);
+    }
+
+    #[test]
+    fn test_sample() {
+        let scanners = parse_scanners(include_str!("../input/sample")).unwrap();
+        let map = BeaconMap::build(&scanners).unwrap();
+        assert_eq!(map.beacons.len(), 79);
+        assert_eq!(map.max_scanner_distance(), 3621);
+    }
+}
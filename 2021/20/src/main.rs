@@ -0,0 +1,175 @@
+use std::fmt;
+use std::io::{self, Read};
+
+#[derive(Debug, Clone)]
+struct Algorithm(Vec<bool>);
+
+impl Algorithm {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let v: Vec<bool> = st.trim().chars().map(|ch| ch == '#').collect();
+        if v.len() != 512 {
+            return Err(format!("expected 512 entries, found {}", v.len()).into());
+        }
+        Ok(Algorithm(v))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Image {
+    pixels: Vec<Vec<bool>>,
+    // Every pixel outside of `pixels` stretching off to infinity. This flips
+    // each step when the algorithm maps 0 to lit and 511 to dark.
+    background: bool,
+}
+
+impl Image {
+    fn parse(st: &str) -> Self {
+        let pixels = st
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.chars().map(|ch| ch == '#').collect())
+            .collect();
+        Image {
+            pixels,
+            background: false,
+        }
+    }
+
+    fn get(&self, r: i32, c: i32) -> bool {
+        self.pixels
+            .get(r as usize)
+            .and_then(|row| row.get(c as usize))
+            .cloned()
+            .unwrap_or(self.background)
+    }
+
+    fn enhance(&self, algorithm: &Algorithm) -> Image {
+        let rows = self.pixels.len() as i32;
+        let cols = self.pixels[0].len() as i32;
+
+        // The image grows by one pixel on every side each step.
+        let mut pixels = vec![vec![false; cols as usize + 2]; rows as usize + 2];
+        for r in -1..=rows {
+            for c in -1..=cols {
+                let mut index = 0;
+                for tup_r in -1..=1 {
+                    for tup_c in -1..=1 {
+                        index = (index << 1) | usize::from(self.get(r + tup_r, c + tup_c));
+                    }
+                }
+                pixels[(r + 1) as usize][(c + 1) as usize] = algorithm.0[index];
+            }
+        }
+
+        let background = if self.background {
+            algorithm.0[511]
+        } else {
+            algorithm.0[0]
+        };
+
+        Image { pixels, background }
+    }
+
+    fn lit(&self) -> Option<usize> {
+        if self.background {
+            return None;
+        }
+        Some(self.pixels.iter().flatten().filter(|p| **p).count())
+    }
+}
+
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.pixels {
+            writeln!(
+                f,
+                "{}",
+                line.iter()
+                    .map(|p| if *p { '#' } else { '.' })
+                    .collect::<String>()
+            )?
+        }
+        Ok(())
+    }
+}
+
+fn parse(st: &str) -> Result<(Algorithm, Image), Box<dyn std::error::Error>> {
+    let (algorithm, image) = st.split_once("\n\n").ok_or("no image")?;
+    Ok((Algorithm::parse(algorithm)?, Image::parse(image)))
+}
+
+fn enhance_n(image: &Image, algorithm: &Algorithm, n: usize) -> Image {
+    let mut image = image.clone();
+    for _ in 0..n {
+        image = image.enhance(algorithm);
+    }
+    image
+}
+
+fn main() {
+    let stdin = io::stdin();
+
+    let mut st = String::new();
+    stdin.lock().read_to_string(&mut st).unwrap();
+
+    let (algorithm, image) = parse(&st).unwrap();
+
+    // Part 1
+    let twice = enhance_n(&image, &algorithm, 2);
+    println!("{}", twice.lit().unwrap());
+
+    // Part 2
+    let fifty = enhance_n(&image, &algorithm, 50);
+    println!("{}", fifty.lit().unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enhance() {
+        let (algorithm, image) = parse(include_str!("../input/sample")).unwrap();
+        assert_eq!(image.lit(), Some(10));
+
+        let once = image.enhance(&algorithm);
+        assert_eq!(
+            once.to_string(),
+            ".##.##.
+#..#.#.
+##.#..#
+####..#
+.#..##.
+..##..#
+...#.#.
+"
+        );
+
+        assert_eq!(enhance_n(&image, &algorithm, 2).lit(), Some(35));
+        assert_eq!(enhance_n(&image, &algorithm, 50).lit(), Some(3351));
+    }
+
+    #[test]
+    fn test_flipping_background() {
+        // Lights everything with a dark neighbourhood and darkens everything
+        // with a lit one, so the infinite background blinks.
+        let mut st = String::from("#");
+        st.push_str(&".".repeat(511));
+        let algorithm = Algorithm::parse(&st).unwrap();
+
+        let image = Image::parse("...\n.#.\n...");
+        let once = image.enhance(&algorithm);
+        assert!(once.background);
+        assert_eq!(once.lit(), None);
+
+        let twice = once.enhance(&algorithm);
+        assert!(!twice.background);
+        assert!(twice.lit().is_some());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(Algorithm::parse("#.#").is_err());
+        assert!(parse("#.#\n#..").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Player {
+    position: u32,
+    score: u32,
+}
+
+impl Player {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let (_, position) = st.split_once(": ").ok_or("no starting position")?;
+        Ok(Player {
+            position: position.trim().parse()?,
+            score: 0,
+        })
+    }
+
+    fn advance(&self, roll: u32) -> Player {
+        let position = (self.position + roll - 1) % 10 + 1;
+        Player {
+            position,
+            score: self.score + position,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DeterministicDie {
+    next: u32,
+    rolls: u32,
+}
+
+impl DeterministicDie {
+    fn new() -> Self {
+        DeterministicDie { next: 1, rolls: 0 }
+    }
+
+    fn roll(&mut self) -> u32 {
+        let r = self.next;
+        self.next = self.next % 100 + 1;
+        self.rolls += 1;
+        r
+    }
+}
+
+// Plays with the deterministic die and returns the losing score multiplied by
+// the number of rolls.
+fn practice(players: [Player; 2]) -> u32 {
+    let mut players = players;
+    let mut die = DeterministicDie::new();
+    let mut turn = 0;
+
+    loop {
+        let roll = die.roll() + die.roll() + die.roll();
+        players[turn] = players[turn].advance(roll);
+        if players[turn].score >= 1000 {
+            return players[1 - turn].score * die.rolls;
+        }
+        turn = 1 - turn;
+    }
+}
+
+// How many of the 27 universes from three rolls of the Dirac die land on each
+// total.
+const DIRAC_ROLLS: [(u32, u64); 7] = [(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)];
+
+struct Multiverse(HashMap<(Player, Player), (u64, u64)>);
+
+impl Multiverse {
+    // Counts the universes in which (the player about to move, the other
+    // player) win, memoised on the state of both players.
+    fn wins(&mut self, current: Player, other: Player) -> (u64, u64) {
+        if let Some(w) = self.0.get(&(current, other)) {
+            return *w;
+        }
+
+        let mut wins = (0, 0);
+        for (roll, universes) in DIRAC_ROLLS {
+            let moved = current.advance(roll);
+            if moved.score >= 21 {
+                wins.0 += universes;
+            } else {
+                let (other_wins, moved_wins) = self.wins(other, moved);
+                wins.0 += moved_wins * universes;
+                wins.1 += other_wins * universes;
+            }
+        }
+
+        self.0.insert((current, other), wins);
+        wins
+    }
+}
+
+fn dirac(players: [Player; 2]) -> u64 {
+    let mut multiverse = Multiverse(HashMap::new());
+    let (a, b) = multiverse.wins(players[0], players[1]);
+    a.max(b)
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let players: Vec<Player> = stdin
+        .lock()
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| !line.is_empty())
+        .map(|line| Player::parse(&line).unwrap())
+        .collect();
+    let players = [players[0], players[1]];
+
+    // Part 1
+    println!("{}", practice(players));
+
+    // Part 2
+    println!("{}", dirac(players));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> [Player; 2] {
+        let mut lines = include_str!("../input/sample").lines();
+        [
+            Player::parse(lines.next().unwrap()).unwrap(),
+            Player::parse(lines.next().unwrap()).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_parse() {
+        let players = example();
+        assert_eq!(players[0].position, 4);
+        assert_eq!(players[1].position, 8);
+        assert!(Player::parse("Player 1").is_err());
+    }
+
+    #[test]
+    fn test_advance() {
+        let p = Player {
+            position: 4,
+            score: 0,
+        };
+        assert_eq!(p.advance(6).position, 10);
+        assert_eq!(p.advance(6).advance(15).position, 5);
+        assert_eq!(p.advance(6).advance(15).score, 15);
+    }
+
+    #[test]
+    fn test_practice() {
+        assert_eq!(practice(example()), 739785);
+    }
+
+    #[test]
+    fn test_dirac() {
+        let players = example();
+        let mut multiverse = Multiverse(HashMap::new());
+        assert_eq!(
+            multiverse.wins(players[0], players[1]),
+            (444356092776315, 341960390180808)
+        );
+        assert_eq!(dirac(players), 444356092776315);
+    }
+}
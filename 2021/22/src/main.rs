@@ -0,0 +1,248 @@
+use std::io::{self, BufRead};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Range(i64, i64);
+
+impl Range {
+    fn parse(st: &str) -> Result<Range, Box<dyn std::error::Error>> {
+        let mut sp = st.split("..");
+        Ok(Range(
+            sp.next().ok_or("no low")?.parse()?,
+            sp.next().ok_or("no hi")?.parse()?,
+        ))
+    }
+
+    fn len(&self) -> i64 {
+        self.1 - self.0 + 1
+    }
+
+    fn intersect(&self, other: &Range) -> Option<Range> {
+        let low = self.0.max(other.0);
+        let hi = self.1.min(other.1);
+        if low <= hi {
+            Some(Range(low, hi))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cuboid {
+    x: Range,
+    y: Range,
+    z: Range,
+}
+
+impl Cuboid {
+    fn parse(st: &str) -> Result<Cuboid, Box<dyn std::error::Error>> {
+        let mut sp = st.split(',');
+        let mut axis = |name: &str| -> Result<Range, Box<dyn std::error::Error>> {
+            let part = sp.next().ok_or(format!("no {}", name))?;
+            Range::parse(part.trim_start_matches(name).trim_start_matches('='))
+        };
+        Ok(Cuboid {
+            x: axis("x")?,
+            y: axis("y")?,
+            z: axis("z")?,
+        })
+    }
+
+    fn volume(&self) -> i64 {
+        self.x.len() * self.y.len() * self.z.len()
+    }
+
+    fn intersect(&self, other: &Cuboid) -> Option<Cuboid> {
+        Some(Cuboid {
+            x: self.x.intersect(&other.x)?,
+            y: self.y.intersect(&other.y)?,
+            z: self.z.intersect(&other.z)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Step {
+    on: bool,
+    cuboid: Cuboid,
+}
+
+impl Step {
+    fn parse(st: &str) -> Result<Step, Box<dyn std::error::Error>> {
+        let (state, cuboid) = st.split_once(' ').ok_or("no state")?;
+        let on = match state {
+            "on" => true,
+            "off" => false,
+            _ => return Err(format!("unexpected state: {}", state).into()),
+        };
+        Ok(Step {
+            on,
+            cuboid: Cuboid::parse(cuboid)?,
+        })
+    }
+}
+
+// The set of lit cubes, kept as a list of cuboids with signed weights so that
+// the weighted volumes sum to the number of lit cubes. Intersections are
+// added with the opposite sign to cancel out anything counted twice.
+#[derive(Debug, Default)]
+struct Reactor(Vec<(Cuboid, i64)>);
+
+impl Reactor {
+    fn apply(&mut self, step: &Step) {
+        let mut added = vec![];
+        for (cuboid, sign) in &self.0 {
+            if let Some(overlap) = cuboid.intersect(&step.cuboid) {
+                added.push((overlap, -sign));
+            }
+        }
+
+        if step.on {
+            added.push((step.cuboid, 1));
+        }
+
+        self.0.extend(added);
+    }
+
+    fn lit(&self) -> i64 {
+        self.0.iter().map(|(c, sign)| c.volume() * sign).sum()
+    }
+
+    fn lit_within(&self, region: &Cuboid) -> i64 {
+        self.0
+            .iter()
+            .filter_map(|(c, sign)| c.intersect(region).map(|o| o.volume() * sign))
+            .sum()
+    }
+}
+
+fn reboot(steps: &[Step]) -> Reactor {
+    let mut reactor = Reactor::default();
+    for step in steps {
+        reactor.apply(step);
+    }
+    reactor
+}
+
+const INITIALIZATION_REGION: Cuboid = Cuboid {
+    x: Range(-50, 50),
+    y: Range(-50, 50),
+    z: Range(-50, 50),
+};
+
+fn main() {
+    let stdin = io::stdin();
+    let steps: Vec<Step> = stdin
+        .lock()
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| !line.is_empty())
+        .map(|line| Step::parse(&line).unwrap())
+        .collect();
+
+    let reactor = reboot(&steps);
+
+    // Part 1
+    println!("{}", reactor.lit_within(&INITIALIZATION_REGION));
+
+    // Part 2
+    println!("{}", reactor.lit());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn parse_steps(st: &str) -> Vec<Step> {
+        st.lines().map(|line| Step::parse(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let step = Step::parse("off x=-54112..-39298,y=-85059..-49293,z=-27449..7877").unwrap();
+        assert!(!step.on);
+        assert_eq!(step.cuboid.x, Range(-54112, -39298));
+        assert_eq!(step.cuboid.z, Range(-27449, 7877));
+
+        assert!(Step::parse("toggle x=1..2,y=1..2,z=1..2").is_err());
+        assert!(Step::parse("on x=1..2,y=1..2").is_err());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let a = Cuboid::parse("x=10..12,y=10..12,z=10..12").unwrap();
+        let b = Cuboid::parse("x=11..13,y=11..13,z=11..13").unwrap();
+        let c = Cuboid::parse("x=13..14,y=10..12,z=10..12").unwrap();
+
+        assert_eq!(
+            a.intersect(&b),
+            Some(Cuboid::parse("x=11..12,y=11..12,z=11..12").unwrap())
+        );
+        assert_eq!(a.intersect(&c), None);
+        assert_eq!(a.volume(), 27);
+    }
+
+    #[test]
+    fn test_small_example() {
+        let steps = parse_steps(include_str!("../input/sample"));
+
+        let mut reactor = Reactor::default();
+        let expected = [27, 27 + 19, 27 + 19 - 8, 39];
+        for (step, lit) in steps.iter().zip(expected) {
+            reactor.apply(step);
+            assert_eq!(reactor.lit(), lit);
+        }
+        assert_eq!(reactor.lit_within(&INITIALIZATION_REGION), 39);
+    }
+
+    #[test]
+    fn test_matches_dense_grid() {
+        let steps = parse_steps(
+            "on x=-5..3,y=-2..4,z=0..6
+off x=-1..1,y=-1..1,z=-1..1
+on x=2..8,y=-6..0,z=3..5
+off x=0..6,y=0..6,z=0..6
+on x=-3..-1,y=-3..-1,z=-3..-1
+on x=-10..10,y=2..2,z=2..2
+off x=-8..-4,y=-8..8,z=-8..8",
+        );
+
+        let mut grid: HashSet<(i64, i64, i64)> = HashSet::new();
+        for step in &steps {
+            let c = step.cuboid;
+            for x in c.x.0..=c.x.1 {
+                for y in c.y.0..=c.y.1 {
+                    for z in c.z.0..=c.z.1 {
+                        if step.on {
+                            grid.insert((x, y, z));
+                        } else {
+                            grid.remove(&(x, y, z));
+                        }
+                    }
+                }
+            }
+        }
+
+        let reactor = reboot(&steps);
+        assert_eq!(reactor.lit(), grid.len() as i64);
+
+        let region = Cuboid::parse("x=-2..2,y=-2..2,z=-2..2").unwrap();
+        let in_region = grid
+            .iter()
+            .filter(|(x, y, z)| x.abs() <= 2 && y.abs() <= 2 && z.abs() <= 2)
+            .count();
+        assert_eq!(reactor.lit_within(&region), in_region as i64);
+    }
+
+    #[test]
+    fn test_large_coordinates() {
+        let steps = parse_steps(
+            "on x=-100000..100000,y=-100000..100000,z=-100000..100000
+off x=0..100000,y=-100000..100000,z=-100000..100000",
+        );
+        let reactor = reboot(&steps);
+        assert_eq!(reactor.lit(), 100000 * 200001 * 200001);
+        assert_eq!(reactor.lit_within(&INITIALIZATION_REGION), 50 * 101 * 101);
+    }
+}
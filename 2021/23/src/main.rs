@@ -0,0 +1,324 @@
+use std::{
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt,
+    io::{self, Read},
+};
+
+const HALLWAY: usize = 11;
+const ROOMS: usize = 4;
+const MAX_DEPTH: usize = 4;
+
+// Every cell of the burrow, one byte each: the hallway first and then each
+// room from the top down. 0 is empty and 1 to 4 are amphipods A to D.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Burrow {
+    cells: [u8; HALLWAY + ROOMS * MAX_DEPTH],
+    depth: u8,
+}
+
+fn energy(kind: u8) -> u32 {
+    10_u32.pow(kind as u32 - 1)
+}
+
+fn door(room: usize) -> usize {
+    2 + 2 * room
+}
+
+impl Burrow {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let cells = |line: &str| -> Vec<u8> {
+            line.chars()
+                .filter(|c| c.is_ascii_uppercase() || *c == '.')
+                .map(|c| match c {
+                    '.' => 0,
+                    c => c as u8 - b'A' + 1,
+                })
+                .collect()
+        };
+
+        let mut lines = st.lines().skip(1);
+        let hallway = cells(lines.next().ok_or("no hallway")?);
+        if hallway.len() != HALLWAY {
+            return Err(
+                format!("expected a hallway of {}, found {}", HALLWAY, hallway.len()).into(),
+            );
+        }
+
+        let mut rows: Vec<Vec<u8>> = vec![];
+        for line in lines {
+            let row = cells(line);
+            match row.len() {
+                0 => continue,
+                ROOMS => rows.push(row),
+                n => return Err(format!("expected {} rooms, found {}", ROOMS, n).into()),
+            }
+        }
+
+        if rows.is_empty() || rows.len() > MAX_DEPTH {
+            return Err(format!("unsupported room depth: {}", rows.len()).into());
+        }
+
+        let mut burrow = Burrow {
+            cells: [0; HALLWAY + ROOMS * MAX_DEPTH],
+            depth: rows.len() as u8,
+        };
+        burrow.cells[..HALLWAY].copy_from_slice(&hallway);
+        for (d, row) in rows.iter().enumerate() {
+            for (r, kind) in row.iter().enumerate() {
+                burrow.cells[Self::slot(r, d)] = *kind;
+            }
+        }
+        Ok(burrow)
+    }
+
+    // Inserts the two folded-up lines of the diagram for part 2.
+    fn unfold(&self) -> Burrow {
+        let mut burrow = Burrow {
+            cells: [0; HALLWAY + ROOMS * MAX_DEPTH],
+            depth: 4,
+        };
+        // #D#C#B#A#
+        // #D#B#A#C#
+        let inserted = [(4, 4), (3, 2), (2, 1), (1, 3)];
+        for (r, (second, third)) in inserted.iter().enumerate() {
+            burrow.cells[Self::slot(r, 0)] = self.cells[Self::slot(r, 0)];
+            burrow.cells[Self::slot(r, 1)] = *second;
+            burrow.cells[Self::slot(r, 2)] = *third;
+            burrow.cells[Self::slot(r, 3)] = self.cells[Self::slot(r, 1)];
+        }
+        burrow
+    }
+
+    fn slot(room: usize, d: usize) -> usize {
+        HALLWAY + room * MAX_DEPTH + d
+    }
+
+    fn room(&self, room: usize) -> impl DoubleEndedIterator<Item = u8> + ExactSizeIterator + '_ {
+        (0..self.depth as usize).map(move |d| self.cells[Self::slot(room, d)])
+    }
+
+    fn solved(&self) -> bool {
+        (0..ROOMS).all(|r| self.room(r).all(|k| k as usize == r + 1))
+    }
+
+    // A room only accepts amphipods once everything left in it belongs there.
+    fn accepting(&self, room: usize) -> bool {
+        self.room(room).all(|k| k == 0 || k as usize == room + 1)
+    }
+
+    fn hallway_clear(&self, from: usize, to: usize) -> bool {
+        let (lo, hi) = if from < to {
+            (from + 1, to)
+        } else {
+            (to, from - 1)
+        };
+        (lo..=hi).all(|h| self.cells[h] == 0)
+    }
+
+    fn moved(&self, from: usize, to: usize) -> Burrow {
+        let mut next = *self;
+        next.cells[to] = self.cells[from];
+        next.cells[from] = 0;
+        next
+    }
+
+    fn moves(&self) -> Vec<(Burrow, u32)> {
+        let mut v = vec![];
+
+        // Hallway to its own room, as deep as it will go.
+        for h in 0..HALLWAY {
+            let kind = self.cells[h];
+            if kind == 0 {
+                continue;
+            }
+            let room = kind as usize - 1;
+            if !self.accepting(room) || !self.hallway_clear(h, door(room)) {
+                continue;
+            }
+            if let Some(d) = self.room(room).rposition(|k| k == 0) {
+                let steps = h.abs_diff(door(room)) + d + 1;
+                v.push((
+                    self.moved(h, Self::slot(room, d)),
+                    steps as u32 * energy(kind),
+                ));
+            }
+        }
+
+        // Top of a room out to any hallway cell not directly outside a room.
+        for room in 0..ROOMS {
+            if self.accepting(room) {
+                continue;
+            }
+            let d = match self.room(room).position(|k| k != 0) {
+                Some(d) => d,
+                None => continue,
+            };
+            let kind = self.cells[Self::slot(room, d)];
+            for h in 0..HALLWAY {
+                if (0..ROOMS).any(|r| door(r) == h) || !self.hallway_clear(door(room), h) {
+                    continue;
+                }
+                if self.cells[h] != 0 {
+                    continue;
+                }
+                let steps = h.abs_diff(door(room)) + d + 1;
+                v.push((
+                    self.moved(Self::slot(room, d), h),
+                    steps as u32 * energy(kind),
+                ));
+            }
+        }
+
+        v
+    }
+
+    fn organize(&self) -> Option<u32> {
+        let mut distances: HashMap<Burrow, u32> = HashMap::from([(*self, 0)]);
+        let mut q: BinaryHeap<(i64, Burrow)> = BinaryHeap::from([(0, *self)]);
+        let mut visited = HashSet::new();
+
+        while let Some((_, cur)) = q.pop() {
+            if !visited.insert(cur) {
+                continue;
+            }
+
+            let cur_dist = distances[&cur];
+            if cur.solved() {
+                return Some(cur_dist);
+            }
+
+            for (next, cost) in cur.moves() {
+                let next_dist = cur_dist + cost;
+                if distances.get(&next).is_none_or(|d| next_dist < *d) {
+                    distances.insert(next, next_dist);
+                    q.push((-(next_dist as i64), next));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl fmt::Display for Burrow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cell = |k: u8| match k {
+            0 => '.',
+            k => (b'A' + k - 1) as char,
+        };
+
+        writeln!(f, "{}", "#".repeat(HALLWAY + 2))?;
+        writeln!(
+            f,
+            "#{}#",
+            self.cells[..HALLWAY]
+                .iter()
+                .map(|k| cell(*k))
+                .collect::<String>()
+        )?;
+        for d in 0..self.depth as usize {
+            let edge = if d == 0 { "##" } else { "  " };
+            write!(f, "{}", edge)?;
+            for r in 0..ROOMS {
+                write!(f, "#{}", cell(self.cells[Self::slot(r, d)]))?;
+            }
+            writeln!(f, "#{}", edge.trim())?;
+        }
+        writeln!(f, "  {}", "#".repeat(ROOMS * 2 + 1))
+    }
+}
+
+fn main() {
+    let stdin = io::stdin();
+
+    let mut st = String::new();
+    stdin.lock().read_to_string(&mut st).unwrap();
+
+    let burrow = Burrow::parse(&st).unwrap();
+    println!("{}", burrow);
+
+    // Part 1
+    println!("{:?}", burrow.organize().unwrap());
+
+    // Part 2
+    let unfolded = burrow.unfold();
+    println!("{}", unfolded);
+    println!("{:?}", unfolded.organize().unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display() {
+        let st = include_str!("../input/sample");
+        let burrow = Burrow::parse(st).unwrap();
+        assert_eq!(burrow.depth, 2);
+        assert_eq!(burrow.to_string(), st);
+
+        assert!(Burrow::parse("#############\n#...........#\n###B#C#B###\n").is_err());
+    }
+
+    #[test]
+    fn test_moves() {
+        let burrow = Burrow::parse(
+            "#############
+#.....D.....#
+###B#.#C#.###
+  #A#B#C#.#
+  #########
+",
+        )
+        .unwrap();
+
+        // D can walk straight home, and only the B blocking room A can
+        // leave, to the hallway cells on its side of D.
+        let moves = burrow.moves();
+        assert_eq!(moves.len(), 4);
+        assert!(moves
+            .iter()
+            .any(|(b, cost)| b.cells[Burrow::slot(3, 1)] == 4 && *cost == 5000));
+        assert!(moves.iter().any(|(b, cost)| b.cells[0] == 2 && *cost == 30));
+        assert!(!burrow.solved());
+    }
+
+    #[test]
+    fn test_solved() {
+        let burrow = Burrow::parse(
+            "#############
+#...........#
+###A#B#C#D###
+  #A#B#C#D#
+  #########
+",
+        )
+        .unwrap();
+        assert!(burrow.solved());
+        assert_eq!(burrow.organize(), Some(0));
+    }
+
+    #[test]
+    fn test_example() {
+        let burrow = Burrow::parse(include_str!("../input/sample")).unwrap();
+        assert_eq!(burrow.organize(), Some(12521));
+    }
+
+    #[test]
+    fn test_example_unfolded() {
+        let burrow = Burrow::parse(include_str!("../input/sample")).unwrap();
+        let unfolded = burrow.unfold();
+        assert_eq!(
+            unfolded.to_string(),
+            "#############
+#...........#
+###B#C#B#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########
+"
+        );
+        assert_eq!(unfolded.organize(), Some(44169));
+    }
+}
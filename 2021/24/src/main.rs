@@ -0,0 +1,539 @@
+use std::collections::HashSet;
+use std::io::{self, Read};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Register {
+    W,
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        use Register::*;
+        match st {
+            "w" => Ok(W),
+            "x" => Ok(X),
+            "y" => Ok(Y),
+            "z" => Ok(Z),
+            _ => Err(format!("unexpected register: {}", st).into()),
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Register(Register),
+    Literal(i64),
+}
+
+impl Operand {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match Register::parse(st) {
+            Ok(r) => Ok(Operand::Register(r)),
+            Err(_) => Ok(Operand::Literal(st.parse()?)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Inp(Register),
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Div(Register, Operand),
+    Mod(Register, Operand),
+    Eql(Register, Operand),
+}
+
+impl Instruction {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        use Instruction::*;
+
+        let mut sp = st.split_whitespace();
+        let opcode = sp.next().ok_or("no opcode")?;
+        let a = Register::parse(sp.next().ok_or("no register")?)?;
+        if opcode == "inp" {
+            return Ok(Inp(a));
+        }
+
+        let b = Operand::parse(sp.next().ok_or("no operand")?)?;
+        match opcode {
+            "add" => Ok(Add(a, b)),
+            "mul" => Ok(Mul(a, b)),
+            "div" => Ok(Div(a, b)),
+            "mod" => Ok(Mod(a, b)),
+            "eql" => Ok(Eql(a, b)),
+            _ => Err(format!("unexpected opcode: {}", opcode).into()),
+        }
+    }
+
+    fn target(&self) -> Register {
+        use Instruction::*;
+        match self {
+            Inp(a) | Add(a, _) | Mul(a, _) | Div(a, _) | Mod(a, _) | Eql(a, _) => *a,
+        }
+    }
+
+    fn operand(&self) -> Option<Operand> {
+        use Instruction::*;
+        match self {
+            Inp(_) => None,
+            Add(_, b) | Mul(_, b) | Div(_, b) | Mod(_, b) | Eql(_, b) => Some(*b),
+        }
+    }
+}
+
+fn parse_program(st: &str) -> Result<Vec<Instruction>, Box<dyn std::error::Error>> {
+    st.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Instruction::parse)
+        .collect()
+}
+
+type Registers = [i64; 4];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Alu {
+    registers: Registers,
+    pc: usize,
+}
+
+impl Alu {
+    fn get(&self, operand: &Operand) -> i64 {
+        match operand {
+            Operand::Register(r) => self.registers[r.index()],
+            Operand::Literal(n) => *n,
+        }
+    }
+
+    // Executes a single non-input instruction.
+    fn step(&mut self, inst: &Instruction) -> Result<(), String> {
+        use Instruction::*;
+
+        let a = self.registers[inst.target().index()];
+        let b = inst.operand().map(|o| self.get(&o)).unwrap_or(0);
+        let v = match inst {
+            Inp(_) => return Err(format!("unexpected input at pc {}", self.pc)),
+            Add(_, _) => a + b,
+            Mul(_, _) => a * b,
+            Div(_, _) if b == 0 => return Err(format!("division by zero at pc {}", self.pc)),
+            Div(_, _) => a / b,
+            Mod(_, _) if a < 0 || b <= 0 => {
+                return Err(format!("invalid modulo {} % {} at pc {}", a, b, self.pc))
+            }
+            Mod(_, _) => a % b,
+            Eql(_, _) => i64::from(a == b),
+        };
+        self.registers[inst.target().index()] = v;
+        self.pc += 1;
+        Ok(())
+    }
+
+    // Runs until the next `inp` instruction or the end of the program.
+    fn run_to_input(&mut self, program: &[Instruction]) -> Result<(), String> {
+        while let Some(inst) = program.get(self.pc) {
+            if let Instruction::Inp(_) = inst {
+                break;
+            }
+            self.step(inst)?;
+        }
+        Ok(())
+    }
+
+    fn run(program: &[Instruction], inputs: &[i64]) -> Result<Registers, String> {
+        let mut alu = Alu::default();
+        let mut inputs = inputs.iter();
+        loop {
+            alu.run_to_input(program)?;
+            match program.get(alu.pc) {
+                Some(Instruction::Inp(r)) => {
+                    alu.registers[r.index()] = *inputs.next().ok_or("ran out of input")?;
+                    alu.pc += 1;
+                }
+                _ => return Ok(alu.registers),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Interval(i128, i128);
+
+impl Interval {
+    fn exact(n: i64) -> Self {
+        Interval(n as i128, n as i128)
+    }
+
+    fn contains(&self, n: i64) -> bool {
+        self.0 <= n as i128 && n as i128 <= self.1
+    }
+
+    fn add(&self, other: &Interval) -> Interval {
+        Interval(
+            self.0.saturating_add(other.0),
+            self.1.saturating_add(other.1),
+        )
+    }
+
+    fn mul(&self, other: &Interval) -> Interval {
+        let products = [
+            self.0.saturating_mul(other.0),
+            self.0.saturating_mul(other.1),
+            self.1.saturating_mul(other.0),
+            self.1.saturating_mul(other.1),
+        ];
+        Interval(
+            *products.iter().min().unwrap(),
+            *products.iter().max().unwrap(),
+        )
+    }
+
+    fn div(&self, other: &Interval) -> Interval {
+        // Only positive divisors keep the bounds monotonic; anything else is
+        // unconstrained.
+        if other.0 <= 0 {
+            return Interval(i128::MIN, i128::MAX);
+        }
+        let quotients = [
+            self.0 / other.0,
+            self.0 / other.1,
+            self.1 / other.0,
+            self.1 / other.1,
+        ];
+        Interval(
+            *quotients.iter().min().unwrap(),
+            *quotients.iter().max().unwrap(),
+        )
+    }
+
+    fn modulo(&self, other: &Interval) -> Interval {
+        if other.0 == other.1 && other.0 > 0 && self.0 >= 0 && self.0 / other.0 == self.1 / other.0
+        {
+            return Interval(self.0 % other.0, self.1 % other.0);
+        }
+        Interval(0, (other.1 - 1).max(0).min(self.1.max(0)))
+    }
+
+    fn eql(&self, other: &Interval) -> Interval {
+        if self.0 == self.1 && other.0 == other.1 && self.0 == other.0 {
+            Interval(1, 1)
+        } else if self.1 < other.0 || other.1 < self.0 {
+            Interval(0, 0)
+        } else {
+            Interval(0, 1)
+        }
+    }
+}
+
+// Bounds every register after running the rest of `program` from `alu`,
+// assuming each remaining input is a digit from 1 to 9.
+fn analyse(program: &[Instruction], alu: &Alu) -> [Interval; 4] {
+    use Instruction::*;
+
+    let mut registers = alu.registers.map(Interval::exact);
+    for inst in &program[alu.pc..] {
+        let a = registers[inst.target().index()];
+        let b = match inst.operand() {
+            Some(Operand::Register(r)) => registers[r.index()],
+            Some(Operand::Literal(n)) => Interval::exact(n),
+            None => Interval(1, 9),
+        };
+        registers[inst.target().index()] = match inst {
+            Inp(_) => b,
+            Add(_, _) => a.add(&b),
+            Mul(_, _) => a.mul(&b),
+            Div(_, _) => a.div(&b),
+            Mod(_, _) => a.modulo(&b),
+            Eql(_, _) => a.eql(&b),
+        };
+    }
+    registers
+}
+
+// For every pc, which registers may be read before they are next written.
+// Dead registers can't affect the outcome, so they are zeroed before states
+// are compared.
+fn liveness(program: &[Instruction]) -> Vec<[bool; 4]> {
+    use Instruction::*;
+
+    let mut live = vec![[false; 4]; program.len() + 1];
+    // z is checked once the program finishes.
+    live[program.len()][Register::Z.index()] = true;
+
+    for (pc, inst) in program.iter().enumerate().rev() {
+        let mut l = live[pc + 1];
+        let target = inst.target().index();
+        match inst {
+            Inp(_) => l[target] = false,
+            // mul a 0 overwrites a without reading it.
+            Mul(_, Operand::Literal(0)) => l[target] = false,
+            _ => l[target] = true,
+        }
+        if let Some(Operand::Register(r)) = inst.operand() {
+            l[r.index()] = true;
+        }
+        live[pc] = l;
+    }
+    live
+}
+
+struct Search<'a> {
+    program: &'a [Instruction],
+    live: Vec<[bool; 4]>,
+    digits: Vec<i64>,
+    dead: HashSet<(usize, Registers)>,
+}
+
+impl<'a> Search<'a> {
+    fn new(program: &'a [Instruction], digits: Vec<i64>) -> Self {
+        Search {
+            program,
+            live: liveness(program),
+            digits,
+            dead: HashSet::new(),
+        }
+    }
+
+    fn key(&self, alu: &Alu) -> (usize, Registers) {
+        let mut registers = alu.registers;
+        for (i, r) in registers.iter_mut().enumerate() {
+            if !self.live[alu.pc][i] {
+                *r = 0;
+            }
+        }
+        (alu.pc, registers)
+    }
+
+    // Depth-first search over input digits in preference order, pruning any
+    // state from which interval analysis proves z can't end up at 0.
+    fn find(&mut self, mut alu: Alu, prefix: &mut Vec<i64>) -> bool {
+        if alu.run_to_input(self.program).is_err() {
+            return false;
+        }
+
+        let target = match self.program.get(alu.pc) {
+            Some(Instruction::Inp(r)) => *r,
+            _ => return alu.registers[Register::Z.index()] == 0,
+        };
+
+        let key = self.key(&alu);
+        if self.dead.contains(&key) {
+            return false;
+        }
+        if !analyse(self.program, &alu)[Register::Z.index()].contains(0) {
+            self.dead.insert(key);
+            return false;
+        }
+
+        for d in self.digits.clone() {
+            let mut next = alu.clone();
+            next.registers[target.index()] = d;
+            next.pc += 1;
+
+            prefix.push(d);
+            if self.find(next, prefix) {
+                return true;
+            }
+            prefix.pop();
+        }
+
+        self.dead.insert(key);
+        false
+    }
+}
+
+fn find_model_number(program: &[Instruction], digits: Vec<i64>) -> Option<String> {
+    let mut prefix = vec![];
+    if Search::new(program, digits).find(Alu::default(), &mut prefix) {
+        Some(prefix.iter().map(|d| d.to_string()).collect())
+    } else {
+        None
+    }
+}
+
+fn largest_model_number(program: &[Instruction]) -> Option<String> {
+    find_model_number(program, (1..=9).rev().collect())
+}
+
+fn smallest_model_number(program: &[Instruction]) -> Option<String> {
+    find_model_number(program, (1..=9).collect())
+}
+
+fn digits(st: &str) -> Vec<i64> {
+    st.chars().map(|c| c.to_digit(10).unwrap() as i64).collect()
+}
+
+fn main() {
+    let stdin = io::stdin();
+
+    let mut st = String::new();
+    stdin.lock().read_to_string(&mut st).unwrap();
+
+    let program = parse_program(&st).unwrap();
+
+    // Part 1
+    let largest = largest_model_number(&program).unwrap();
+    assert_eq!(Alu::run(&program, &digits(&largest)).unwrap()[3], 0);
+    println!("{}", largest);
+
+    // Part 2
+    let smallest = smallest_model_number(&program).unwrap();
+    assert_eq!(Alu::run(&program, &digits(&smallest)).unwrap()[3], 0);
+    println!("{}", smallest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds a MONAD-shaped program from per-digit (div z, add x, add y)
+    // parameters.
+    fn monad(params: &[(i64, i64, i64)]) -> Vec<Instruction> {
+        let mut st = String::new();
+        for (div, add_x, add_y) in params {
+            st.push_str(&format!(
+                "inp w
+mul x 0
+add x z
+mod x 26
+div z {}
+add x {}
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y {}
+mul y x
+add z y
+",
+                div, add_x, add_y
+            ));
+        }
+        parse_program(&st).unwrap()
+    }
+
+    #[test]
+    fn test_negate() {
+        let program = parse_program("inp x\nmul x -1").unwrap();
+        assert_eq!(Alu::run(&program, &[7]).unwrap(), [0, -7, 0, 0]);
+    }
+
+    #[test]
+    fn test_three_times() {
+        let program = parse_program("inp z\ninp x\nmul z 3\neql z x").unwrap();
+        assert_eq!(Alu::run(&program, &[2, 6]).unwrap()[3], 1);
+        assert_eq!(Alu::run(&program, &[2, 7]).unwrap()[3], 0);
+    }
+
+    #[test]
+    fn test_binary() {
+        let program = parse_program(
+            "inp w
+add z w
+mod z 2
+div w 2
+add y w
+mod y 2
+div w 2
+add x w
+mod x 2
+div w 2
+mod w 2",
+        )
+        .unwrap();
+        assert_eq!(Alu::run(&program, &[11]).unwrap(), [1, 0, 1, 1]);
+        assert_eq!(Alu::run(&program, &[6]).unwrap(), [0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Instruction::parse("sub x 1").is_err());
+        assert!(Instruction::parse("add q 1").is_err());
+
+        let program = parse_program("inp x\ndiv x y").unwrap();
+        assert!(Alu::run(&program, &[1]).is_err());
+        assert!(Alu::run(&program, &[]).is_err());
+
+        let program = parse_program("inp x\nmod x -2").unwrap();
+        assert!(Alu::run(&program, &[1]).is_err());
+    }
+
+    #[test]
+    fn test_interval() {
+        let digit = Interval(1, 9);
+        assert_eq!(digit.add(&Interval::exact(-3)), Interval(-2, 6));
+        assert_eq!(digit.mul(&Interval(-1, 2)), Interval(-9, 18));
+        assert_eq!(Interval(30, 60).div(&Interval::exact(26)), Interval(1, 2));
+        assert_eq!(
+            Interval(27, 30).modulo(&Interval::exact(26)),
+            Interval(1, 4)
+        );
+        assert_eq!(
+            Interval(20, 30).modulo(&Interval::exact(26)),
+            Interval(0, 25)
+        );
+        assert_eq!(digit.eql(&Interval::exact(10)), Interval(0, 0));
+        assert_eq!(digit.eql(&Interval::exact(5)), Interval(0, 1));
+    }
+
+    #[test]
+    fn test_liveness() {
+        let program = parse_program("inp w\nmul x 0\nadd x z\nadd z x").unwrap();
+        let live = liveness(&program);
+        // Nothing is read before being written except z.
+        assert_eq!(live[0], [false, false, false, true]);
+        assert_eq!(live[1], [false, false, false, true]);
+        assert_eq!(live[2], [false, true, false, true]);
+        assert_eq!(live[3], [false, true, false, true]);
+    }
+
+    #[test]
+    fn test_model_numbers() {
+        // Pushes and pops pair up so that each pop digit is a fixed offset
+        // from its push digit, as in the real puzzle input.
+        let program = monad(&[
+            (1, 12, 4),
+            (1, 11, 11),
+            (1, 13, 7),
+            (26, -2, 5),
+            (1, 10, 2),
+            (26, -8, 9),
+            (1, 14, 15),
+            (26, -9, 3),
+            (1, 15, 1),
+            (1, 12, 3),
+            (26, -3, 8),
+            (26, -5, 2),
+            (26, -11, 6),
+            (26, -7, 1),
+        ]);
+
+        let largest = largest_model_number(&program).unwrap();
+        assert_eq!(largest, "99499339999596");
+        assert_eq!(Alu::run(&program, &digits(&largest)).unwrap()[3], 0);
+
+        let smallest = smallest_model_number(&program).unwrap();
+        assert_eq!(smallest, "41167117511111");
+        assert_eq!(Alu::run(&program, &digits(&smallest)).unwrap()[3], 0);
+
+        assert_ne!(Alu::run(&program, &digits("99499339999597")).unwrap()[3], 0);
+    }
+
+    #[test]
+    fn test_no_model_number() {
+        // The only pop can never match its push, so z is never 0.
+        let program = monad(&[(1, 12, 4), (26, -20, 5)]);
+        assert_eq!(largest_model_number(&program), None);
+    }
+}
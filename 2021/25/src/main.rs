@@ -0,0 +1,181 @@
+use std::fmt;
+use std::io::{self, Read};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    East,
+    South,
+    Empty,
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Cell::*;
+        match self {
+            East => write!(f, ">"),
+            South => write!(f, "v"),
+            Empty => write!(f, "."),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SeaFloor(Vec<Vec<Cell>>);
+
+impl SeaFloor {
+    fn parse(st: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let v = st
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.chars()
+                    .map(|ch| match ch {
+                        '>' => Ok(Cell::East),
+                        'v' => Ok(Cell::South),
+                        '.' => Ok(Cell::Empty),
+                        _ => Err(format!("unexpected cell: {}", ch)),
+                    })
+                    .collect::<Result<Vec<Cell>, String>>()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(SeaFloor(v))
+    }
+
+    // Moves one herd at once: every member looks at the grid as it was before
+    // any of them moved.
+    fn move_herd(&self, herd: Cell, tup: (usize, usize)) -> (SeaFloor, usize) {
+        let rows = self.0.len();
+        let cols = self.0[0].len();
+
+        let mut ret = self.clone();
+        let mut moved = 0;
+        for r in 0..rows {
+            for c in 0..cols {
+                if self.0[r][c] != herd {
+                    continue;
+                }
+
+                // Sea cucumbers that move off an edge reappear on the other.
+                let (nr, nc) = ((r + tup.0) % rows, (c + tup.1) % cols);
+                if self.0[nr][nc] == Cell::Empty {
+                    ret.0[r][c] = Cell::Empty;
+                    ret.0[nr][nc] = herd;
+                    moved += 1;
+                }
+            }
+        }
+        (ret, moved)
+    }
+
+    fn iterate(&self) -> (SeaFloor, usize) {
+        let (east, east_moved) = self.move_herd(Cell::East, (0, 1));
+        let (south, south_moved) = east.move_herd(Cell::South, (1, 0));
+        (south, east_moved + south_moved)
+    }
+
+    fn first_stationary_step(&self) -> usize {
+        let mut floor = self.clone();
+        let mut step = 0;
+        loop {
+            step += 1;
+            let (next, moved) = floor.iterate();
+            if moved == 0 {
+                return step;
+            }
+            floor = next;
+        }
+    }
+}
+
+impl fmt::Display for SeaFloor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.0 {
+            for cell in line {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let stdin = io::stdin();
+
+    let mut st = String::new();
+    stdin.lock().read_to_string(&mut st).unwrap();
+
+    let floor = SeaFloor::parse(&st).unwrap();
+
+    // Part 1
+    println!("{}", floor.first_stationary_step());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_row() {
+        let floor = SeaFloor::parse("...>>>>>...").unwrap();
+        let (once, moved) = floor.iterate();
+        assert_eq!(once.to_string(), "...>>>>.>..\n");
+        assert_eq!(moved, 1);
+
+        let (twice, moved) = once.iterate();
+        assert_eq!(twice.to_string(), "...>>>.>.>.\n");
+        assert_eq!(moved, 2);
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let floor = SeaFloor::parse(
+            "...>...
+.......
+......>
+v.....>
+......>
+.......
+..vvv..",
+        )
+        .unwrap();
+
+        let (once, _) = floor.iterate();
+        assert_eq!(
+            once.to_string(),
+            "..vv>..
+.......
+>......
+v.....>
+>......
+.......
+....v..
+"
+        );
+    }
+
+    #[test]
+    fn test_example() {
+        let floor = SeaFloor::parse(include_str!("../input/sample")).unwrap();
+        let (once, _) = floor.iterate();
+        assert_eq!(
+            once.to_string(),
+            "....>.>v.>
+v.v>.>v.v.
+>v>>..>v..
+>>v>v>.>.v
+.>v.v...v.
+v>>.>vvv..
+..v...>>..
+vv...>>vv.
+>.v.v..v.v
+"
+        );
+        assert_eq!(floor.first_stationary_step(), 58);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(SeaFloor::parse("..<..").is_err());
+    }
+}
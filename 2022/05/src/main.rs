@@ -0,0 +1,182 @@
+use std::io::{self, Read};
+
+// Reads a drawing whose last line labels each column, returning the cells
+// above each label from the bottom up. Columns are found from where the labels
+// sit, so any fixed-width layout works and short lines are treated as padded
+// with spaces.
+fn parse_columns(st: &str) -> Result<Vec<Vec<char>>, Box<dyn std::error::Error>> {
+    let mut lines: Vec<&str> = st.lines().filter(|line| !line.trim().is_empty()).collect();
+    let labels = lines.pop().ok_or("no column labels")?;
+
+    let offsets: Vec<usize> = labels
+        .char_indices()
+        .filter(|(_, c)| !c.is_whitespace())
+        .map(|(i, _)| i)
+        .filter(|i| *i == 0 || labels.as_bytes()[i - 1] == b' ')
+        .collect();
+
+    let mut columns = vec![vec![]; offsets.len()];
+    for line in lines.iter().rev() {
+        for (column, offset) in columns.iter_mut().zip(&offsets) {
+            match line.chars().nth(*offset) {
+                Some(' ') | None => (),
+                Some(c) => column.push(c),
+            }
+        }
+    }
+    Ok(columns)
+}
+
+#[derive(Debug, PartialEq)]
+struct Move {
+    n: usize,
+    from: usize,
+    to: usize,
+}
+
+impl Move {
+    fn parse(st: &str) -> Result<Move, Box<dyn std::error::Error>> {
+        let sp: Vec<&str> = st.split_whitespace().collect();
+        match sp.as_slice() {
+            ["move", n, "from", from, "to", to] => Ok(Move {
+                n: n.parse()?,
+                from: from.parse()?,
+                to: to.parse()?,
+            }),
+            _ => Err(format!("unexpected move: {}", st).into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Stacks(Vec<Vec<char>>);
+
+#[derive(Debug, Clone, Copy)]
+enum Crane {
+    // Moves crates one at a time, reversing their order.
+    CrateMover9000,
+    // Moves all crates at once, keeping their order.
+    CrateMover9001,
+}
+
+impl Stacks {
+    fn apply(&mut self, m: &Move, crane: Crane) -> Result<(), Box<dyn std::error::Error>> {
+        if m.to == 0 || m.to > self.0.len() {
+            return Err(format!("no stack {}", m.to).into());
+        }
+
+        let from = self
+            .0
+            .get_mut(m.from.wrapping_sub(1))
+            .ok_or(format!("no stack {}", m.from))?;
+        if from.len() < m.n {
+            return Err(format!("stack {} only has {} crates", m.from, from.len()).into());
+        }
+
+        let mut lifted = from.split_off(from.len() - m.n);
+        if let Crane::CrateMover9000 = crane {
+            lifted.reverse();
+        }
+
+        self.0[m.to - 1].extend(lifted);
+        Ok(())
+    }
+
+    fn tops(&self) -> String {
+        self.0.iter().filter_map(|s| s.last()).collect()
+    }
+}
+
+fn parse(st: &str) -> Result<(Stacks, Vec<Move>), Box<dyn std::error::Error>> {
+    let (diagram, moves) = st.split_once("\n\n").ok_or("no moves")?;
+    let moves = moves
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(Move::parse)
+        .collect::<Result<_, _>>()?;
+    Ok((Stacks(parse_columns(diagram)?), moves))
+}
+
+fn rearrange(stacks: &Stacks, moves: &[Move], crane: Crane) -> String {
+    let mut stacks = stacks.clone();
+    for m in moves {
+        stacks.apply(m, crane).unwrap();
+    }
+    stacks.tops()
+}
+
+fn main() {
+    let mut stdin = io::stdin();
+    let mut buf = String::new();
+    stdin.read_to_string(&mut buf).unwrap();
+
+    let (stacks, moves) = parse(&buf).unwrap();
+
+    println!("{}", rearrange(&stacks, &moves, Crane::CrateMover9000));
+    println!("{}", rearrange(&stacks, &moves, Crane::CrateMover9001));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_columns() {
+        let (diagram, _) = include_str!("../simple_example")
+            .split_once("\n\n")
+            .unwrap();
+        let columns = parse_columns(diagram).unwrap();
+        assert_eq!(
+            columns,
+            vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]
+        );
+
+        // Other column widths, with trailing whitespace trimmed.
+        let columns = parse_columns("    B\nA   C\n1   2").unwrap();
+        assert_eq!(columns, vec![vec!['A'], vec!['C', 'B']]);
+    }
+
+    #[test]
+    fn test_parse_move() {
+        assert_eq!(
+            Move::parse("move 3 from 1 to 3").unwrap(),
+            Move {
+                n: 3,
+                from: 1,
+                to: 3
+            }
+        );
+        assert!(Move::parse("move 3 from 1").is_err());
+    }
+
+    #[test]
+    fn test_example() {
+        let (stacks, moves) = parse(include_str!("../simple_example")).unwrap();
+        assert_eq!(rearrange(&stacks, &moves, Crane::CrateMover9000), "CMZ");
+        assert_eq!(rearrange(&stacks, &moves, Crane::CrateMover9001), "MCD");
+    }
+
+    #[test]
+    fn test_bad_move() {
+        let (mut stacks, _) = parse(include_str!("../simple_example")).unwrap();
+        let m = Move {
+            n: 4,
+            from: 1,
+            to: 2,
+        };
+        assert!(stacks.apply(&m, Crane::CrateMover9000).is_err());
+        let m = Move {
+            n: 1,
+            from: 0,
+            to: 2,
+        };
+        assert!(stacks.apply(&m, Crane::CrateMover9000).is_err());
+        let m = Move {
+            n: 1,
+            from: 1,
+            to: 4,
+        };
+        assert!(stacks.apply(&m, Crane::CrateMover9000).is_err());
+        assert_eq!(stacks.tops(), "NDP");
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::{self, Read};
+
+// Returns how many characters have been read once the last `window` of them
+// are all different.
+fn find_marker(st: &str, window: usize) -> Option<usize> {
+    let bytes = st.trim().as_bytes();
+
+    // How many times each byte appears in the current window, and how many
+    // distinct bytes that makes.
+    let mut counts = [0_usize; 256];
+    let mut distinct = 0;
+
+    for (i, b) in bytes.iter().enumerate() {
+        counts[*b as usize] += 1;
+        if counts[*b as usize] == 1 {
+            distinct += 1;
+        }
+
+        if i >= window {
+            let dropped = bytes[i - window] as usize;
+            counts[dropped] -= 1;
+            if counts[dropped] == 0 {
+                distinct -= 1;
+            }
+        }
+
+        if distinct == window {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+fn main() {
+    let mut stdin = io::stdin();
+    let mut buf = String::new();
+    stdin.read_to_string(&mut buf).unwrap();
+
+    println!("{}", find_marker(&buf, 4).unwrap());
+    println!("{}", find_marker(&buf, 14).unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        for (st, packet, message) in [
+            (include_str!("../simple_example"), 7, 19),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ] {
+            assert_eq!(find_marker(st, 4), Some(packet));
+            assert_eq!(find_marker(st, 14), Some(message));
+        }
+    }
+
+    #[test]
+    fn test_no_marker() {
+        assert_eq!(find_marker("aaaaaaaa", 4), None);
+        assert_eq!(find_marker("abc", 4), None);
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+#[derive(Default, Debug)]
+struct Dir {
+    name: String,
+    files: Vec<(String, u64)>,
+    dirs: Vec<Dir>,
+}
+
+impl Dir {
+    fn new(name: &str) -> Dir {
+        Dir {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // Replays a `cd`/`ls` transcript starting from `/`.
+    fn parse(st: &str) -> Result<Dir, Box<dyn std::error::Error>> {
+        let mut root = Dir::new("/");
+        let mut cwd: Vec<String> = vec![];
+
+        for line in st.lines().filter(|line| !line.is_empty()) {
+            let sp: Vec<&str> = line.split_whitespace().collect();
+            match sp.as_slice() {
+                ["$", "cd", "/"] => cwd.clear(),
+                ["$", "cd", ".."] => {
+                    cwd.pop().ok_or("cd .. from /")?;
+                }
+                ["$", "cd", name] => {
+                    root.resolve_mut(&cwd)?.subdir(name)?;
+                    cwd.push(name.to_string());
+                }
+                ["$", "ls"] => (),
+                ["dir", name] => {
+                    let dir = root.resolve_mut(&cwd)?;
+                    if dir.subdir(name).is_err() {
+                        dir.dirs.push(Dir::new(name));
+                    }
+                }
+                [size, name] => {
+                    let dir = root.resolve_mut(&cwd)?;
+                    if !dir.files.iter().any(|(n, _)| n == name) {
+                        dir.files.push((name.to_string(), size.parse()?));
+                    }
+                }
+                _ => return Err(format!("unexpected line: {}", line).into()),
+            }
+        }
+
+        Ok(root)
+    }
+
+    fn subdir(&mut self, name: &str) -> Result<&mut Dir, Box<dyn std::error::Error>> {
+        let parent = self.name.clone();
+        self.dirs
+            .iter_mut()
+            .find(|d| d.name == name)
+            .ok_or_else(|| format!("no directory {} in {}", name, parent).into())
+    }
+
+    fn resolve_mut(&mut self, path: &[String]) -> Result<&mut Dir, Box<dyn std::error::Error>> {
+        let mut dir = self;
+        for name in path {
+            dir = dir.subdir(name)?;
+        }
+        Ok(dir)
+    }
+
+    fn size(&self) -> u64 {
+        let files: u64 = self.files.iter().map(|(_, size)| size).sum();
+        let dirs: u64 = self.dirs.iter().map(|d| d.size()).sum();
+        files + dirs
+    }
+
+    // The total size of every directory in this tree, this one first.
+    fn sizes(&self) -> Vec<u64> {
+        let mut v = vec![self.size()];
+        for d in &self.dirs {
+            v.extend(d.sizes());
+        }
+        v
+    }
+
+    fn sum_of_dirs_under(&self, threshold: u64) -> u64 {
+        self.sizes().iter().filter(|s| **s <= threshold).sum()
+    }
+
+    fn smallest_to_free(&self, disk: u64, needed: u64) -> Option<u64> {
+        let unused = disk.checked_sub(self.size())?;
+        let to_free = needed.saturating_sub(unused);
+        self.sizes().into_iter().filter(|s| *s >= to_free).min()
+    }
+
+    fn write_tree(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        writeln!(f, "{}- {} (dir)", indent, self.name)?;
+        for d in &self.dirs {
+            d.write_tree(f, depth + 1)?;
+        }
+        for (name, size) in &self.files {
+            writeln!(f, "{}  - {} (file, size={})", indent, name, size)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+fn main() {
+    let mut stdin = io::stdin();
+    let mut buf = String::new();
+    stdin.read_to_string(&mut buf).unwrap();
+
+    let root = Dir::parse(&buf).unwrap();
+    //println!("{}", root);
+
+    println!("{}", root.sum_of_dirs_under(100000));
+    println!("{}", root.smallest_to_free(70000000, 30000000).unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let root = Dir::parse(include_str!("../simple_example")).unwrap();
+        assert_eq!(
+            root.to_string(),
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - d (dir)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+"
+        );
+
+        assert!(Dir::parse("$ cd /\n$ cd nope").is_err());
+        assert!(Dir::parse("$ cd ..").is_err());
+    }
+
+    #[test]
+    fn test_sizes() {
+        let root = Dir::parse(include_str!("../simple_example")).unwrap();
+        assert_eq!(root.size(), 48381165);
+        assert_eq!(root.sizes(), vec![48381165, 94853, 584, 24933642]);
+    }
+
+    #[test]
+    fn test_example() {
+        let root = Dir::parse(include_str!("../simple_example")).unwrap();
+        assert_eq!(root.sum_of_dirs_under(100000), 95437);
+        assert_eq!(root.smallest_to_free(70000000, 30000000), Some(24933642));
+    }
+
+    #[test]
+    fn test_repeated_ls() {
+        let mut st = include_str!("../simple_example").to_string();
+        st.push_str("$ cd /\n$ ls\ndir a\n14848514 b.txt\n");
+        let root = Dir::parse(&st).unwrap();
+        assert_eq!(root.size(), 48381165);
+    }
+}
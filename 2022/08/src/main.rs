@@ -0,0 +1,119 @@
+use std::io::{self, BufRead};
+
+#[derive(Debug)]
+struct Forest(Vec<Vec<u32>>);
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+impl Forest {
+    fn parse(lines: impl Iterator<Item = String>) -> Result<Forest, Box<dyn std::error::Error>> {
+        let v = lines
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.chars()
+                    .map(|ch| ch.to_digit(10).ok_or(format!("not a height: {}", ch)))
+                    .collect::<Result<Vec<u32>, String>>()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Forest(v))
+    }
+
+    // The heights seen walking out from (r, c) towards the edge, nearest first.
+    fn line_of_sight(&self, r: usize, c: usize, tup: (i32, i32)) -> Vec<u32> {
+        let mut v = vec![];
+        let mut pt = (r as i32 + tup.0, c as i32 + tup.1);
+        while let Some(h) = self
+            .0
+            .get(pt.0 as usize)
+            .and_then(|row| row.get(pt.1 as usize))
+        {
+            v.push(*h);
+            pt = (pt.0 + tup.0, pt.1 + tup.1);
+        }
+        v
+    }
+
+    fn visible(&self, r: usize, c: usize) -> bool {
+        let height = self.0[r][c];
+        DIRECTIONS
+            .iter()
+            .any(|tup| self.line_of_sight(r, c, *tup).iter().all(|h| *h < height))
+    }
+
+    fn scenic_score(&self, r: usize, c: usize) -> usize {
+        let height = self.0[r][c];
+        DIRECTIONS
+            .iter()
+            .map(|tup| {
+                let sight = self.line_of_sight(r, c, *tup);
+                // Stop at the first tree at least as tall, counting it.
+                match sight.iter().position(|h| *h >= height) {
+                    Some(i) => i + 1,
+                    None => sight.len(),
+                }
+            })
+            .product()
+    }
+
+    fn points(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.0.len()).flat_map(move |r| (0..self.0[r].len()).map(move |c| (r, c)))
+    }
+
+    fn count_visible(&self) -> usize {
+        self.points().filter(|(r, c)| self.visible(*r, *c)).count()
+    }
+
+    fn best_scenic_score(&self) -> usize {
+        self.points()
+            .map(|(r, c)| self.scenic_score(r, c))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let forest = Forest::parse(stdin.lock().lines().map(|line| line.unwrap())).unwrap();
+
+    println!("{}", forest.count_visible());
+    println!("{}", forest.best_scenic_score());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Forest {
+        Forest::parse(
+            include_str!("../simple_example")
+                .lines()
+                .map(|line| line.to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_visible() {
+        let forest = example();
+        assert!(forest.visible(1, 1));
+        assert!(forest.visible(1, 2));
+        assert!(!forest.visible(1, 3));
+        assert!(!forest.visible(2, 2));
+        assert!(forest.visible(0, 4));
+        assert_eq!(forest.count_visible(), 21);
+    }
+
+    #[test]
+    fn test_scenic_score() {
+        let forest = example();
+        assert_eq!(forest.scenic_score(1, 2), 4);
+        assert_eq!(forest.scenic_score(3, 2), 8);
+        assert_eq!(forest.scenic_score(0, 0), 0);
+        assert_eq!(forest.best_scenic_score(), 8);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(Forest::parse(["12a".to_string()].into_iter()).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashSet,
+    io::{self, BufRead},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Motion {
+    direction: (i32, i32),
+    steps: u32,
+}
+
+impl Motion {
+    fn parse(st: &str) -> Result<Motion, Box<dyn std::error::Error>> {
+        let (direction, steps) = st.split_once(' ').ok_or("no steps")?;
+        let direction = match direction {
+            "R" => (1, 0),
+            "L" => (-1, 0),
+            "U" => (0, 1),
+            "D" => (0, -1),
+            _ => return Err(format!("unexpected direction: {}", direction).into()),
+        };
+        Ok(Motion {
+            direction,
+            steps: steps.parse()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rope(Vec<(i32, i32)>);
+
+impl Rope {
+    fn new(knots: usize) -> Rope {
+        Rope(vec![(0, 0); knots])
+    }
+
+    fn tail(&self) -> (i32, i32) {
+        *self.0.last().unwrap()
+    }
+
+    // Moves the head one step, then lets every following knot catch up with
+    // the one ahead of it.
+    fn step(&mut self, direction: (i32, i32)) {
+        self.0[0].0 += direction.0;
+        self.0[0].1 += direction.1;
+
+        for i in 1..self.0.len() {
+            let ahead = self.0[i - 1];
+            let knot = &mut self.0[i];
+            let (dx, dy) = (ahead.0 - knot.0, ahead.1 - knot.1);
+
+            // Still touching, including diagonally and overlapping.
+            if dx.abs() <= 1 && dy.abs() <= 1 {
+                break;
+            }
+
+            knot.0 += dx.signum();
+            knot.1 += dy.signum();
+        }
+    }
+}
+
+fn tail_visits(motions: &[Motion], knots: usize) -> usize {
+    let mut rope = Rope::new(knots);
+    let mut visited: HashSet<(i32, i32)> = HashSet::from([rope.tail()]);
+
+    for m in motions {
+        for _ in 0..m.steps {
+            rope.step(m.direction);
+            visited.insert(rope.tail());
+        }
+    }
+
+    visited.len()
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let motions: Vec<Motion> = stdin
+        .lock()
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| !line.is_empty())
+        .map(|line| Motion::parse(&line).unwrap())
+        .collect();
+
+    println!("{}", tail_visits(&motions, 2));
+    println!("{}", tail_visits(&motions, 10));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(st: &str) -> Vec<Motion> {
+        st.lines()
+            .map(|line| Motion::parse(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Motion::parse("U 4").unwrap(),
+            Motion {
+                direction: (0, 1),
+                steps: 4
+            }
+        );
+        assert!(Motion::parse("X 4").is_err());
+        assert!(Motion::parse("R").is_err());
+    }
+
+    #[test]
+    fn test_step() {
+        let mut rope = Rope::new(2);
+        rope.step((1, 0));
+        assert_eq!(rope.tail(), (0, 0));
+        rope.step((1, 0));
+        assert_eq!(rope.tail(), (1, 0));
+
+        // Diagonal catch-up.
+        rope.step((0, 1));
+        assert_eq!(rope.tail(), (1, 0));
+        rope.step((0, 1));
+        assert_eq!(rope.tail(), (2, 1));
+    }
+
+    #[test]
+    fn test_example() {
+        let motions = parse_all(include_str!("../simple_example"));
+        assert_eq!(tail_visits(&motions, 2), 13);
+        assert_eq!(tail_visits(&motions, 10), 1);
+    }
+
+    #[test]
+    fn test_larger_example() {
+        let motions = parse_all(include_str!("../larger_example"));
+        assert_eq!(tail_visits(&motions, 10), 36);
+    }
+}
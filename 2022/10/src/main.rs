@@ -0,0 +1,259 @@
+use std::{
+    fmt,
+    io::{self, BufRead},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Noop,
+    Addx(i32),
+}
+
+impl Instruction {
+    fn parse(st: &str) -> Result<Instruction, Box<dyn std::error::Error>> {
+        let sp: Vec<&str> = st.split_whitespace().collect();
+        match sp.as_slice() {
+            ["noop"] => Ok(Instruction::Noop),
+            ["addx", n] => Ok(Instruction::Addx(n.parse()?)),
+            _ => Err(format!("unexpected instruction: {}", st).into()),
+        }
+    }
+
+    fn cycles(&self) -> usize {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Addx(_) => 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Cpu {
+    x: i32,
+    cycle: usize,
+}
+
+impl Cpu {
+    fn new() -> Self {
+        Cpu { x: 1, cycle: 0 }
+    }
+
+    // Runs the program, calling `hook` with the cycle number (counting from 1)
+    // and the value of X *during* every cycle.
+    fn run(&mut self, program: &[Instruction], mut hook: impl FnMut(usize, i32)) {
+        for inst in program {
+            for _ in 0..inst.cycles() {
+                self.cycle += 1;
+                hook(self.cycle, self.x);
+            }
+            if let Instruction::Addx(n) = inst {
+                self.x += n;
+            }
+        }
+    }
+}
+
+fn signal_strength(program: &[Instruction]) -> i32 {
+    let mut sum = 0;
+    Cpu::new().run(program, |cycle, x| {
+        if cycle >= 20 && (cycle - 20) % 40 == 0 {
+            sum += cycle as i32 * x;
+        }
+    });
+    sum
+}
+
+const WIDTH: usize = 40;
+const HEIGHT: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Dot {
+    On,
+    Off,
+}
+
+impl fmt::Display for Dot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Dot::*;
+        match self {
+            On => write!(f, "#"),
+            Off => write!(f, "."),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Screen(Vec<Vec<Dot>>);
+
+// Letters are 4 pixels wide and 6 tall, with a blank column between each.
+const LETTER_WIDTH: usize = 5;
+
+const FONT: [(char, &str); 16] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+impl Screen {
+    #[cfg(test)]
+    fn parse(st: &str) -> Screen {
+        Screen(
+            st.lines()
+                .map(|line| {
+                    line.chars()
+                        .map(|ch| if ch == '#' { Dot::On } else { Dot::Off })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn render(program: &[Instruction]) -> Screen {
+        let mut screen = Screen(vec![vec![Dot::Off; WIDTH]; HEIGHT]);
+        Cpu::new().run(program, |cycle, x| {
+            let row = (cycle - 1) / WIDTH;
+            let col = (cycle - 1) % WIDTH;
+            // The sprite is three pixels wide, centred on X.
+            if row < HEIGHT && (col as i32 - x).abs() <= 1 {
+                screen.0[row][col] = Dot::On;
+            }
+        });
+        screen
+    }
+
+    fn decode(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let width = self.0.first().map_or(0, |row| row.len());
+        (0..width.div_ceil(LETTER_WIDTH))
+            .map(|i| {
+                let glyph: String = self
+                    .0
+                    .iter()
+                    .flat_map(|row| {
+                        (0..LETTER_WIDTH - 1).map(move |c| match row.get(i * LETTER_WIDTH + c) {
+                            Some(Dot::On) => '#',
+                            _ => '.',
+                        })
+                    })
+                    .collect();
+                FONT.iter()
+                    .find(|(_, g)| *g == glyph)
+                    .map(|(c, _)| *c)
+                    .ok_or_else(|| format!("unknown letter at {}: {}", i, glyph).into())
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.0 {
+            for dot in line {
+                write!(f, "{}", dot)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let program: Vec<Instruction> = stdin
+        .lock()
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| !line.is_empty())
+        .map(|line| Instruction::parse(&line).unwrap())
+        .collect();
+
+    println!("{}", signal_strength(&program));
+
+    let screen = Screen::render(&program);
+    println!("{}", screen);
+    println!("{}", screen.decode().unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Instruction> {
+        include_str!("../simple_example")
+            .lines()
+            .map(|line| Instruction::parse(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_small_program() {
+        let program: Vec<Instruction> = ["noop", "addx 3", "addx -5"]
+            .iter()
+            .map(|line| Instruction::parse(line).unwrap())
+            .collect();
+
+        let mut during = vec![];
+        let mut cpu = Cpu::new();
+        cpu.run(&program, |cycle, x| during.push((cycle, x)));
+        assert_eq!(during, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(cpu.x, -1);
+
+        assert!(Instruction::parse("addx").is_err());
+        assert!(Instruction::parse("subx 3").is_err());
+    }
+
+    #[test]
+    fn test_signal_strength() {
+        let program = example();
+        let mut samples = vec![];
+        Cpu::new().run(&program, |cycle, x| {
+            if [20, 60, 100, 140, 180, 220].contains(&cycle) {
+                samples.push(cycle as i32 * x);
+            }
+        });
+        assert_eq!(samples, vec![420, 1140, 1800, 2940, 2880, 3960]);
+        assert_eq!(signal_strength(&program), 13140);
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            Screen::render(&example()).to_string(),
+            "##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....
+"
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let screen = Screen::parse(
+            "###..####.####.#..#.####.####.#..#..##..
+#..#....#.#....#.#..#....#....#..#.#..#.
+#..#...#..###..##...###..###..####.#....
+###...#...#....#.#..#....#....#..#.#.##.
+#.#..#....#....#.#..#....#....#..#.#..#.
+#..#.####.####.#..#.####.#....#..#..###.
+",
+        );
+        assert_eq!(screen.decode().unwrap(), "RZEKEFHG");
+
+        assert!(Screen::render(&example()).decode().is_err());
+    }
+}
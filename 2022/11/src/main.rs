@@ -0,0 +1,228 @@
+use std::io::{self, Read};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Old,
+    Literal(u64),
+}
+
+impl Operand {
+    fn parse(st: &str) -> Result<Operand, Box<dyn std::error::Error>> {
+        match st {
+            "old" => Ok(Operand::Old),
+            n => Ok(Operand::Literal(n.parse()?)),
+        }
+    }
+
+    fn value(&self, old: u64) -> u64 {
+        match self {
+            Operand::Old => old,
+            Operand::Literal(n) => *n,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expression {
+    Add(Operand, Operand),
+    Mul(Operand, Operand),
+}
+
+impl Expression {
+    fn parse(st: &str) -> Result<Expression, Box<dyn std::error::Error>> {
+        let sp: Vec<&str> = st.split_whitespace().collect();
+        match sp.as_slice() {
+            ["new", "=", a, "+", b] => Ok(Expression::Add(Operand::parse(a)?, Operand::parse(b)?)),
+            ["new", "=", a, "*", b] => Ok(Expression::Mul(Operand::parse(a)?, Operand::parse(b)?)),
+            _ => Err(format!("unexpected operation: {}", st).into()),
+        }
+    }
+
+    fn eval(&self, old: u64) -> u64 {
+        match self {
+            Expression::Add(a, b) => a.value(old) + b.value(old),
+            Expression::Mul(a, b) => a.value(old) * b.value(old),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Monkey {
+    items: Vec<u64>,
+    operation: Expression,
+    divisor: u64,
+    if_true: usize,
+    if_false: usize,
+    inspected: usize,
+}
+
+impl Monkey {
+    fn parse(st: &str) -> Result<Monkey, Box<dyn std::error::Error>> {
+        let mut lines = st.lines().map(|line| line.trim()).skip(1);
+        let mut field = |prefix: &str| -> Result<String, Box<dyn std::error::Error>> {
+            let line = lines.next().ok_or(format!("no {}", prefix))?;
+            Ok(line
+                .strip_prefix(prefix)
+                .ok_or(format!("expected {}, found {}", prefix, line))?
+                .trim()
+                .to_string())
+        };
+
+        let items = field("Starting items:")?
+            .split(", ")
+            .map(|i| i.parse())
+            .collect::<Result<_, _>>()?;
+        let operation = Expression::parse(&field("Operation:")?)?;
+        let divisor = field("Test: divisible by")?.parse()?;
+        let if_true = field("If true: throw to monkey")?.parse()?;
+        let if_false = field("If false: throw to monkey")?.parse()?;
+
+        Ok(Monkey {
+            items,
+            operation,
+            divisor,
+            if_true,
+            if_false,
+            inspected: 0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Relief {
+    // Worry is divided by three after each inspection.
+    DivideByThree,
+    // Worry is only kept modulo the product of every monkey's divisor, which
+    // leaves every divisibility test unchanged.
+    Modulo(u64),
+}
+
+#[derive(Debug, Clone)]
+struct Troop(Vec<Monkey>);
+
+impl Troop {
+    fn parse(st: &str) -> Result<Troop, Box<dyn std::error::Error>> {
+        Ok(Troop(
+            st.split("\n\n")
+                .filter(|block| !block.trim().is_empty())
+                .map(Monkey::parse)
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    fn modulus(&self) -> u64 {
+        self.0.iter().map(|m| m.divisor).product()
+    }
+
+    fn round(&mut self, relief: Relief) {
+        for i in 0..self.0.len() {
+            let items = std::mem::take(&mut self.0[i].items);
+            self.0[i].inspected += items.len();
+
+            for item in items {
+                let monkey = &self.0[i];
+                let worry = match relief {
+                    Relief::DivideByThree => monkey.operation.eval(item) / 3,
+                    Relief::Modulo(m) => monkey.operation.eval(item) % m,
+                };
+                let target = if worry % monkey.divisor == 0 {
+                    monkey.if_true
+                } else {
+                    monkey.if_false
+                };
+                self.0[target].items.push(worry);
+            }
+        }
+    }
+
+    fn monkey_business(&self) -> usize {
+        let mut inspected: Vec<usize> = self.0.iter().map(|m| m.inspected).collect();
+        inspected.sort_unstable();
+        inspected.reverse();
+        inspected.iter().take(2).product()
+    }
+}
+
+fn play(troop: &Troop, rounds: usize, relief: Relief) -> Troop {
+    let mut troop = troop.clone();
+    for _ in 0..rounds {
+        troop.round(relief);
+    }
+    troop
+}
+
+fn main() {
+    let mut stdin = io::stdin();
+    let mut buf = String::new();
+    stdin.read_to_string(&mut buf).unwrap();
+
+    let troop = Troop::parse(&buf).unwrap();
+
+    let relieved = play(&troop, 20, Relief::DivideByThree);
+    println!("{}", relieved.monkey_business());
+
+    let worried = play(&troop, 10000, Relief::Modulo(troop.modulus()));
+    println!("{}", worried.monkey_business());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let troop = Troop::parse(include_str!("../simple_example")).unwrap();
+        assert_eq!(troop.0.len(), 4);
+        assert_eq!(troop.0[1].items, vec![54, 65, 75, 74]);
+        assert_eq!(
+            troop.0[2].operation,
+            Expression::Mul(Operand::Old, Operand::Old)
+        );
+        assert_eq!(troop.0[3].divisor, 17);
+        assert_eq!((troop.0[0].if_true, troop.0[0].if_false), (2, 3));
+        assert_eq!(troop.modulus(), 23 * 19 * 13 * 17);
+
+        assert!(Expression::parse("new = old - 3").is_err());
+        assert!(Monkey::parse("Monkey 0:\n  Starting items: 1\n").is_err());
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(Expression::parse("new = old * 19").unwrap().eval(79), 1501);
+        assert_eq!(Expression::parse("new = old + 6").unwrap().eval(54), 60);
+        assert_eq!(Expression::parse("new = old * old").unwrap().eval(79), 6241);
+    }
+
+    #[test]
+    fn test_first_round() {
+        let mut troop = Troop::parse(include_str!("../simple_example")).unwrap();
+        troop.round(Relief::DivideByThree);
+        assert_eq!(troop.0[0].items, vec![20, 23, 27, 26]);
+        assert_eq!(troop.0[1].items, vec![2080, 25, 167, 207, 401, 1046]);
+        assert!(troop.0[2].items.is_empty());
+    }
+
+    #[test]
+    fn test_relief() {
+        let troop = Troop::parse(include_str!("../simple_example")).unwrap();
+        let relieved = play(&troop, 20, Relief::DivideByThree);
+        let inspected: Vec<usize> = relieved.0.iter().map(|m| m.inspected).collect();
+        assert_eq!(inspected, vec![101, 95, 7, 105]);
+        assert_eq!(relieved.monkey_business(), 10605);
+    }
+
+    #[test]
+    fn test_modulo() {
+        let troop = Troop::parse(include_str!("../simple_example")).unwrap();
+        let relief = Relief::Modulo(troop.modulus());
+
+        let after_twenty: Vec<usize> = play(&troop, 20, relief)
+            .0
+            .iter()
+            .map(|m| m.inspected)
+            .collect();
+        assert_eq!(after_twenty, vec![99, 97, 8, 103]);
+
+        assert_eq!(play(&troop, 10000, relief).monkey_business(), 2713310158);
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, Read},
+};
+
+type Point = (usize, usize);
+
+#[derive(Debug)]
+struct Heightmap {
+    heights: Vec<Vec<u8>>,
+    start: Point,
+    end: Point,
+}
+
+impl Heightmap {
+    fn parse(st: &str) -> Result<Heightmap, Box<dyn std::error::Error>> {
+        let mut start = None;
+        let mut end = None;
+
+        let mut heights = vec![];
+        for (r, line) in st.lines().filter(|line| !line.is_empty()).enumerate() {
+            let mut row = vec![];
+            for (c, ch) in line.chars().enumerate() {
+                let h = match ch {
+                    'S' => {
+                        start = Some((r, c));
+                        'a'
+                    }
+                    'E' => {
+                        end = Some((r, c));
+                        'z'
+                    }
+                    'a'..='z' => ch,
+                    _ => return Err(format!("unexpected height: {}", ch).into()),
+                };
+                row.push(h as u8 - b'a');
+            }
+            heights.push(row);
+        }
+
+        Ok(Heightmap {
+            heights,
+            start: start.ok_or("no start")?,
+            end: end.ok_or("no end")?,
+        })
+    }
+
+    fn neighbors(&self, pt: Point) -> impl Iterator<Item = Point> + '_ {
+        [(0, -1), (-1, 0), (1, 0), (0, 1)]
+            .into_iter()
+            .map(move |tup| {
+                (
+                    (pt.0 as i32 + tup.0) as usize,
+                    (pt.1 as i32 + tup.1) as usize,
+                )
+            })
+            .filter(|n| self.heights.get(n.0).and_then(|row| row.get(n.1)).is_some())
+    }
+
+    // Breadth-first search out from `from` until `done` accepts a cell,
+    // stepping only where `can_step(from_height, to_height)` allows. Returns
+    // the path including both ends.
+    fn bfs(
+        &self,
+        from: Point,
+        can_step: impl Fn(u8, u8) -> bool,
+        done: impl Fn(Point) -> bool,
+    ) -> Option<Vec<Point>> {
+        let mut parents: Vec<Vec<Option<Point>>> =
+            vec![vec![None; self.heights[0].len()]; self.heights.len()];
+        let mut visited = vec![vec![false; self.heights[0].len()]; self.heights.len()];
+        visited[from.0][from.1] = true;
+
+        let mut q = VecDeque::from([from]);
+        while let Some(cur) = q.pop_front() {
+            if done(cur) {
+                let mut path = vec![cur];
+                let mut pt = cur;
+                while let Some(parent) = parents[pt.0][pt.1] {
+                    path.push(parent);
+                    pt = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let height = self.heights[cur.0][cur.1];
+            for n in self.neighbors(cur) {
+                if visited[n.0][n.1] || !can_step(height, self.heights[n.0][n.1]) {
+                    continue;
+                }
+                visited[n.0][n.1] = true;
+                parents[n.0][n.1] = Some(cur);
+                q.push_back(n);
+            }
+        }
+
+        None
+    }
+
+    fn shortest_climb(&self) -> Option<Vec<Point>> {
+        self.bfs(self.start, |from, to| to <= from + 1, |pt| pt == self.end)
+    }
+
+    // Walks down from the end with the climbing rule reversed, so the first
+    // `a` reached is the closest to the end.
+    fn shortest_hike(&self) -> Option<Vec<Point>> {
+        let mut path = self.bfs(
+            self.end,
+            |from, to| from <= to + 1,
+            |pt| self.heights[pt.0][pt.1] == 0,
+        )?;
+        path.reverse();
+        Some(path)
+    }
+
+    fn render(&self, path: &[Point]) -> String {
+        let mut grid = vec![vec!['.'; self.heights[0].len()]; self.heights.len()];
+        for step in path.windows(2) {
+            let (a, b) = (step[0], step[1]);
+            grid[a.0][a.1] = if b.0 > a.0 {
+                'v'
+            } else if b.0 < a.0 {
+                '^'
+            } else if b.1 > a.1 {
+                '>'
+            } else {
+                '<'
+            };
+        }
+        if let Some(last) = path.last() {
+            grid[last.0][last.1] = 'E';
+        }
+
+        let mut st = String::new();
+        for row in grid {
+            st.extend(row);
+            st.push('\n');
+        }
+        st
+    }
+}
+
+impl fmt::Display for Heightmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (r, line) in self.heights.iter().enumerate() {
+            for (c, h) in line.iter().enumerate() {
+                if (r, c) == self.start {
+                    write!(f, "S")?;
+                } else if (r, c) == self.end {
+                    write!(f, "E")?;
+                } else {
+                    write!(f, "{}", (b'a' + h) as char)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let mut stdin = io::stdin();
+    let mut buf = String::new();
+    stdin.read_to_string(&mut buf).unwrap();
+
+    let map = Heightmap::parse(&buf).unwrap();
+
+    let climb = map.shortest_climb().unwrap();
+    println!("{}", map.render(&climb));
+    println!("{}", climb.len() - 1);
+
+    let hike = map.shortest_hike().unwrap();
+    println!("{}", map.render(&hike));
+    println!("{}", hike.len() - 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let st = include_str!("../simple_example");
+        let map = Heightmap::parse(st).unwrap();
+        assert_eq!(map.start, (0, 0));
+        assert_eq!(map.end, (2, 5));
+        assert_eq!(map.to_string(), st);
+
+        assert!(Heightmap::parse("Sab\nabc").is_err());
+        assert!(Heightmap::parse("SaE\nab1").is_err());
+    }
+
+    #[test]
+    fn test_shortest_climb() {
+        let map = Heightmap::parse(include_str!("../simple_example")).unwrap();
+        let climb = map.shortest_climb().unwrap();
+        assert_eq!(climb.len() - 1, 31);
+        assert_eq!(climb[0], map.start);
+        assert_eq!(*climb.last().unwrap(), map.end);
+
+        for step in climb.windows(2) {
+            let (a, b) = (step[0], step[1]);
+            assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+            assert!(map.heights[b.0][b.1] <= map.heights[a.0][a.1] + 1);
+        }
+    }
+
+    #[test]
+    fn test_shortest_hike() {
+        let map = Heightmap::parse(include_str!("../simple_example")).unwrap();
+        let hike = map.shortest_hike().unwrap();
+        assert_eq!(hike.len() - 1, 29);
+        assert_eq!(map.heights[hike[0].0][hike[0].1], 0);
+        assert_eq!(*hike.last().unwrap(), map.end);
+    }
+
+    #[test]
+    fn test_render() {
+        let map = Heightmap::parse(include_str!("../simple_example")).unwrap();
+        let rendered = map.render(&map.shortest_climb().unwrap());
+        assert_eq!(rendered.lines().count(), 5);
+        assert_eq!(rendered.chars().filter(|c| "<>^v".contains(*c)).count(), 31);
+        assert_eq!(rendered.lines().nth(2).unwrap().chars().nth(5), Some('E'));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let map = Heightmap::parse("Sbz\nbcE").unwrap();
+        assert_eq!(map.shortest_climb(), None);
+    }
+}